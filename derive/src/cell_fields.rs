@@ -0,0 +1,228 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{Attribute, Data, DeriveInput, Error, Fields, Visibility};
+
+/// The strategy requested with `#[cell_fields(copy)]` or
+/// `#[cell_fields(default)]`.
+#[derive(Clone, Copy, PartialEq)]
+enum Strategy {
+    Auto,
+    Copy,
+    Default,
+}
+
+fn parse_strategy(attrs: &[Attribute]) -> syn::Result<Strategy> {
+    let mut strategy = Strategy::Auto;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("cell_fields")) {
+        attr.parse_nested_meta(|meta| {
+            let new = if meta.path.is_ident("copy") {
+                Strategy::Copy
+            } else if meta.path.is_ident("default") {
+                Strategy::Default
+            } else {
+                return Err(meta.error("expected `copy` or `default`"));
+            };
+            if strategy != Strategy::Auto {
+                return Err(meta.error("strategy specified more than once"));
+            }
+            strategy = new;
+            Ok(())
+        })?;
+    }
+    Ok(strategy)
+}
+
+/// Options from `#[cell_fields(...)]` attributes on a field.
+#[derive(Default)]
+struct FieldOptions {
+    skip: bool,
+    skip_get: bool,
+}
+
+fn parse_field_options(attrs: &[Attribute]) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("cell_fields")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                options.skip = true;
+            } else if meta.path.is_ident("skip_get") {
+                options.skip_get = true;
+            } else {
+                return Err(meta.error("expected `skip` or `skip_get`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+/// Returns whether a field with visibility `field` is at least as visible
+/// as a trait with visibility `item`, conservatively: the field must be
+/// `pub` or declared with the same visibility.
+fn is_visible(field: &Visibility, item: &Visibility) -> bool {
+    match field {
+        Visibility::Public(_) => true,
+        _ => quote!(#field).to_string() == quote!(#item).to_string(),
+    }
+}
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    input.ident.span(),
+                    "`CellFields` requires a struct with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.ident.span(),
+                "`CellFields` can only be derived for structs",
+            ));
+        }
+    };
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let trait_name = format_ident!("{}CellFields", name);
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    let self_ty = quote!(#name #ty_generics);
+
+    // For non-generic structs, method resolution picks the inherent `Copy`
+    // methods or `CellExt` on its own. Generic structs need an explicit
+    // bound, which defaults to `Default`.
+    let mut strategy = parse_strategy(&input.attrs)?;
+    if strategy == Strategy::Auto && !input.generics.params.is_empty() {
+        strategy = Strategy::Default;
+    }
+    let mut impl_where =
+        where_clause.cloned().unwrap_or_else(|| syn::parse_quote!(where));
+    match strategy {
+        Strategy::Auto => {}
+        Strategy::Copy => impl_where
+            .predicates
+            .push(syn::parse_quote!(#self_ty: ::core::marker::Copy)),
+        Strategy::Default => impl_where
+            .predicates
+            .push(syn::parse_quote!(#self_ty: ::core::default::Default)),
+    }
+
+    let mut decls = Vec::new();
+    let mut defs = Vec::new();
+    for field in fields {
+        let field_options = parse_field_options(&field.attrs)?;
+        if field_options.skip || !is_visible(&field.vis, vis) {
+            continue;
+        }
+        let ident = field.ident.as_ref().unwrap();
+        let unraw = ident.unraw();
+        let ty = &field.ty;
+        let span = ident.span();
+        let get = format_ident!("get_{}", unraw, span = span);
+        let set = format_ident!("set_{}", unraw, span = span);
+        let with = format_ident!("with_{}", unraw, span = span);
+        let with_mut = format_ident!("with_{}_mut", unraw, span = span);
+        let get_doc = format!("Gets a clone of the `{}` field.", unraw);
+        let set_doc = format!("Sets the `{}` field.", unraw);
+        let with_doc =
+            format!("Calls `f` with a reference to the `{}` field.", unraw);
+        let with_mut_doc = format!(
+            "Calls `f` with a mutable reference to the `{}` field.",
+            unraw,
+        );
+
+        if !field_options.skip_get {
+            decls.push(quote! {
+                #[doc = #get_doc]
+                fn #get(&self) -> #ty
+                where
+                    #ty: ::core::clone::Clone;
+            });
+            defs.push(quote! {
+                fn #get(&self) -> #ty
+                where
+                    #ty: ::core::clone::Clone,
+                {
+                    use ::cell_ref::CellExt as _;
+                    self.with(|s| ::core::clone::Clone::clone(&s.#ident))
+                }
+            });
+        }
+
+        decls.push(quote! {
+            #[doc = #set_doc]
+            fn #set(&self, value: #ty);
+
+            #[doc = #with_doc]
+            fn #with<__F, __R>(&self, f: __F) -> __R
+            where
+                __F: ::core::ops::FnOnce(&#ty) -> __R;
+
+            #[doc = #with_mut_doc]
+            fn #with_mut<__F, __R>(&self, f: __F) -> __R
+            where
+                __F: ::core::ops::FnOnce(&mut #ty) -> __R;
+        });
+        defs.push(quote! {
+            fn #set(&self, value: #ty) {
+                use ::cell_ref::CellExt as _;
+                self.with_mut(|s| s.#ident = value);
+            }
+
+            fn #with<__F, __R>(&self, f: __F) -> __R
+            where
+                __F: ::core::ops::FnOnce(&#ty) -> __R,
+            {
+                use ::cell_ref::CellExt as _;
+                self.with(|s| f(&s.#ident))
+            }
+
+            fn #with_mut<__F, __R>(&self, f: __F) -> __R
+            where
+                __F: ::core::ops::FnOnce(&mut #ty) -> __R,
+            {
+                use ::cell_ref::CellExt as _;
+                self.with_mut(|s| f(&mut s.#ident))
+            }
+        });
+    }
+
+    let trait_doc =
+        format!("Provides per-field accessors for `Cell<{}>`.", name);
+    Ok(quote! {
+        #[doc = #trait_doc]
+        #vis trait #trait_name #impl_generics #where_clause {
+            #(#decls)*
+        }
+
+        impl #impl_generics #trait_name #ty_generics
+        for ::cell_ref::Cell<#self_ty>
+        #impl_where
+        {
+            #(#defs)*
+        }
+    })
+}
@@ -0,0 +1,190 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use proc_macro2::{Ident, TokenStream, TokenTree};
+use quote::{format_ident, quote};
+use syn::meta::ParseNestedMeta;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::visit_mut::{self, VisitMut};
+use syn::{Error, Expr, FnArg, ImplItem, ItemImpl, Member, Token};
+
+/// Options from the arguments of `#[interior(...)]`.
+#[derive(Default)]
+pub struct Options {
+    skip: Vec<Member>,
+}
+
+impl Options {
+    pub fn parse(&mut self, meta: ParseNestedMeta<'_>) -> syn::Result<()> {
+        if !meta.path.is_ident("skip") {
+            return Err(meta.error("expected `skip`"));
+        }
+        meta.parse_nested_meta(|meta| {
+            let ident = meta.path.require_ident()?;
+            self.skip.push(Member::Named(ident.clone()));
+            Ok(())
+        })
+    }
+}
+
+pub fn expand(options: Options, mut item: ItemImpl) -> TokenStream {
+    let mut errors = Vec::new();
+    for impl_item in &mut item.items {
+        let method = match impl_item {
+            ImplItem::Fn(method) => method,
+            _ => continue,
+        };
+        let receiver = match method.sig.inputs.first_mut() {
+            Some(FnArg::Receiver(receiver)) => receiver,
+            _ => continue,
+        };
+        if receiver.reference.is_none()
+            || receiver.mutability.is_none()
+            || receiver.colon_token.is_some()
+        {
+            continue;
+        }
+        if let Some(token) = &method.sig.asyncness {
+            errors.push(Error::new(
+                token.span,
+                "`#[interior]` does not support async methods",
+            ));
+            continue;
+        }
+        receiver.mutability = None;
+        receiver.ty = syn::parse_quote!(&Self);
+
+        let mut rewriter = Rewriter {
+            options: &options,
+            fields: Vec::new(),
+            errors: &mut errors,
+        };
+        rewriter.visit_block_mut(&mut method.block);
+        let fields = rewriter.fields;
+
+        let block = &method.block;
+        let mut body = quote!(#block);
+        for (member, ident) in fields.iter().rev() {
+            // The binding may only be used in statements removed by `#[cfg]`.
+            body = quote! {
+                self.#member.with_mut(
+                    |#[allow(unused_variables)] #ident| #body
+                )
+            };
+        }
+        method.block = syn::parse_quote!({
+            #[allow(unused_imports)]
+            use ::cell_ref::CellExt as _;
+            #body
+        });
+    }
+
+    // Emit the item even when there are errors, so that uses of it don’t
+    // cause further errors.
+    let errors = errors.iter().map(Error::to_compile_error);
+    quote! {
+        #item
+        #(#errors)*
+    }
+}
+
+/// Replaces field accesses like `self.field` with references to the values
+/// taken out of the corresponding cells.
+struct Rewriter<'a> {
+    options: &'a Options,
+    fields: Vec<(Member, Ident)>,
+    errors: &'a mut Vec<Error>,
+}
+
+impl Rewriter<'_> {
+    fn binding(&mut self, member: &Member) -> Ident {
+        if let Some((_, ident)) = self.fields.iter().find(|f| f.0 == *member) {
+            return ident.clone();
+        }
+        let ident = match member {
+            Member::Named(name) => format_ident!("__self_{}", name),
+            Member::Unnamed(index) => format_ident!("__self_{}", index),
+        };
+        self.fields.push((member.clone(), ident.clone()));
+        ident
+    }
+}
+
+fn is_self(expr: &Expr) -> bool {
+    match expr {
+        Expr::Path(path) => path.qself.is_none() && path.path.is_ident("self"),
+        _ => false,
+    }
+}
+
+fn contains_self(tokens: TokenStream) -> Option<Ident> {
+    tokens.into_iter().find_map(|tree| match tree {
+        TokenTree::Ident(ident) if ident == "self" => Some(ident),
+        TokenTree::Group(group) => contains_self(group.stream()),
+        _ => None,
+    })
+}
+
+impl VisitMut for Rewriter<'_> {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Field(field) if is_self(&field.base) => {
+                if self.options.skip.contains(&field.member) {
+                    return;
+                }
+                let ident = self.binding(&field.member);
+                *expr = syn::parse_quote!((*#ident));
+            }
+            Expr::MethodCall(call) if is_self(&call.receiver) => {
+                self.errors.push(Error::new_spanned(
+                    &call.receiver,
+                    "cannot call methods on `self` in an `#[interior]` \
+                     method, as its cells may already be in use",
+                ));
+            }
+            _ if is_self(expr) => {
+                self.errors.push(Error::new_spanned(
+                    &*expr,
+                    "`self` can only be used to access fields in an \
+                     `#[interior]` method, as its cells may already be in \
+                     use",
+                ));
+            }
+            _ => visit_mut::visit_expr_mut(self, expr),
+        }
+    }
+
+    fn visit_item_mut(&mut self, _: &mut syn::Item) {
+        // Nested items have their own `self`.
+    }
+
+    fn visit_macro_mut(&mut self, mac: &mut syn::Macro) {
+        let parser = Punctuated::<Expr, Token![,]>::parse_terminated;
+        if let Ok(mut args) = parser.parse2(mac.tokens.clone()) {
+            args.iter_mut().for_each(|arg| self.visit_expr_mut(arg));
+            mac.tokens = quote!(#args);
+        } else if let Some(ident) = contains_self(mac.tokens.clone()) {
+            self.errors.push(Error::new(
+                ident.span(),
+                "`self` can only be used in macros whose arguments are \
+                 expressions in an `#[interior]` method",
+            ));
+        }
+    }
+}
@@ -0,0 +1,175 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![forbid(unsafe_code)]
+
+//! Derive macros for [cell-ref](https://docs.rs/cell-ref).
+//!
+//! This crate is re-exported by cell-ref when its `derive` feature is
+//! enabled; it shouldn’t need to be used directly.
+
+use proc_macro::TokenStream;
+use syn::{DeriveInput, Error, ItemImpl, parse_macro_input};
+
+mod cell_fields;
+mod interior;
+
+/// Generates an extension trait with per-field accessors for
+/// `cell_ref::Cell<S>`.
+///
+/// For a struct `S`, this generates a trait named `SCellFields`, with the
+/// same visibility as `S`, that provides the following methods for each
+/// field `f` of type `T`:
+///
+/// * `get_f(&self) -> T`, which clones the field (requires `T: Clone`).
+/// * `set_f(&self, value: T)`
+/// * `with_f(&self, f: impl FnOnce(&T) -> R) -> R`
+/// * `with_f_mut(&self, f: impl FnOnce(&mut T) -> R) -> R`
+///
+/// These access the struct with `Cell::with` and `Cell::with_mut` if `S` is
+/// `Copy`, or with `CellExt` if `S` is `Default`. Generic structs must
+/// choose one with `#[cell_fields(copy)]` or `#[cell_fields(default)]`
+/// (the default).
+///
+/// Because `get_f` is unavailable when the field type is a concrete
+/// non-`Clone` type, such fields must be annotated with
+/// `#[cell_fields(skip_get)]`.
+///
+/// To preserve encapsulation, accessors are generated only for fields that
+/// are `pub` or have the same visibility as `S`; a private field of a `pub`
+/// struct, for example, gets no accessors. Other fields can be left out
+/// with `#[cell_fields(skip)]`.
+///
+/// # Example
+///
+/// ```rust
+/// use cell_ref::{Cell, CellFields};
+///
+/// #[derive(CellFields, Default)]
+/// struct State {
+///     count: u32,
+///     names: Vec<String>,
+/// }
+///
+/// let state = Cell::new(State::default());
+/// state.set_count(3);
+/// state.with_names_mut(|names| names.push("a".into()));
+/// assert_eq!(state.get_count(), 3);
+/// assert_eq!(state.with_names(Vec::len), 1);
+/// ```
+///
+/// Private fields of a `pub` struct can’t be accessed from other modules:
+///
+/// ```compile_fail
+/// mod bank {
+///     use cell_ref::CellFields;
+///
+///     #[derive(CellFields, Clone, Copy, Default)]
+///     pub struct Account {
+///         pub id: u32,
+///         balance: u32,
+///     }
+/// }
+///
+/// use bank::{Account, AccountCellFields};
+/// let account = cell_ref::Cell::new(Account::default());
+/// account.set_id(1);
+/// account.set_balance(1_000_000);
+/// ```
+#[proc_macro_derive(CellFields, attributes(cell_fields))]
+pub fn derive_cell_fields(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    cell_fields::expand(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// Turns `&mut self` methods into `&self` methods that mutate the fields of
+/// `self` through their cells.
+///
+/// When applied to an `impl` block, this rewrites each method that takes
+/// `&mut self` to take `&self` instead. Every field accessed as `self.f` is
+/// assumed to be a `cell_ref::Cell` (or standard library `Cell`), and the
+/// method body is run within nested calls to `with_mut` on those cells, with
+/// `self.f` referring to the value taken out of the cell. Fields that aren’t
+/// cells can be excluded with `#[interior(skip(f, ...))]`.
+///
+/// Each cell is accessed at most once per call, so within a single method it
+/// is never mutated by two accesses at the same time. To keep it that way,
+/// using `self` for anything other than accessing fields, such as calling a
+/// method (which could access the same cells), is a compile error. `async`
+/// methods are not supported.
+///
+/// The cells are chosen before `#[cfg]` attributes are evaluated, so a field
+/// used only in code that is configured out is still taken out of its cell.
+///
+/// # Example
+///
+/// ```rust
+/// use cell_ref::{Cell, CellExt, interior};
+///
+/// #[derive(Default)]
+/// struct Counter {
+///     count: Cell<u32>,
+///     history: Cell<Vec<u32>>,
+///     step: u32,
+/// }
+///
+/// #[interior(skip(step))]
+/// impl Counter {
+///     fn bump(&mut self) -> u32 {
+///         self.count += self.step;
+///         self.history.push(self.count);
+///         self.count
+///     }
+/// }
+///
+/// let counter = Counter {
+///     step: 2,
+///     ..Counter::default()
+/// };
+/// counter.bump();
+/// assert_eq!(counter.bump(), 4);
+/// assert_eq!(counter.history.get(), [2, 4]);
+/// ```
+///
+/// Calling another method on `self` is rejected:
+///
+/// ```compile_fail
+/// # use cell_ref::{Cell, interior};
+/// # struct Counter {
+/// #     count: Cell<u32>,
+/// # }
+/// #[interior]
+/// impl Counter {
+///     fn reset(&mut self) {
+///         self.count = 0;
+///     }
+///
+///     fn bump(&mut self) {
+///         self.count += 1;
+///         self.reset();
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn interior(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut options = interior::Options::default();
+    let parser = syn::meta::parser(|meta| options.parse(meta));
+    parse_macro_input!(args with parser);
+    let input = parse_macro_input!(input as ItemImpl);
+    interior::expand(options, input).into()
+}
@@ -0,0 +1,115 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::TakeGuard;
+use core::cell::Cell as StdCell;
+
+/// A strategy for accessing the contents of a cell by reference.
+///
+/// [`Cell`]’s inherent methods use [`CopyAccess`], and the methods of
+/// [`CellExt`] use [`DefaultAccess`]. Code that should work with either
+/// strategy can be made generic over this trait:
+///
+/// ```rust
+/// use cell_ref::{Cell, CellAccess, CopyAccess, DefaultAccess};
+///
+/// fn len<A, T>(cell: &Cell<T>) -> usize
+/// where
+///     A: CellAccess<T>,
+///     T: AsRef<[u8]>,
+/// {
+///     A::with(cell, |x| x.as_ref().len())
+/// }
+///
+/// let c1 = Cell::new([1_u8, 2, 3]);
+/// assert!(len::<CopyAccess, _>(&c1) == 3);
+///
+/// let c2 = Cell::new(vec![1_u8, 2]);
+/// assert!(len::<DefaultAccess, _>(&c2) == 2);
+/// ```
+///
+/// [`Cell`]: crate::Cell
+/// [`CellExt`]: crate::CellExt
+pub trait CellAccess<T> {
+    /// Calls `f` with a reference to the contents of `cell`.
+    ///
+    /// If `f` panics, `cell` still holds its original value.
+    fn with<F, R>(cell: &StdCell<T>, f: F) -> R
+    where
+        F: FnOnce(&T) -> R;
+
+    /// Calls `f` with a mutable reference to the contents of `cell`.
+    ///
+    /// If `f` panics, the (possibly modified) value is still stored back in
+    /// `cell`.
+    fn with_mut<F, R>(cell: &StdCell<T>, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R;
+
+    /// Gets a clone of the value held by `cell`.
+    fn get(cell: &StdCell<T>) -> T
+    where
+        T: Clone,
+    {
+        Self::with(cell, T::clone)
+    }
+}
+
+/// Accesses [`Copy`] types by copying the value out of the cell and, for
+/// mutable access, storing it back afterward.
+pub enum CopyAccess {}
+
+impl<T: Copy> CellAccess<T> for CopyAccess {
+    fn with<F, R>(cell: &StdCell<T>, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        f(&cell.get())
+    }
+
+    fn with_mut<F, R>(cell: &StdCell<T>, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        f(&mut TakeGuard::new(cell, cell.get()))
+    }
+
+    fn get(cell: &StdCell<T>) -> T {
+        cell.get()
+    }
+}
+
+/// Accesses [`Default`] types by taking the value out of the cell (leaving
+/// [`Default::default()`] in its place) and storing it back afterward.
+pub enum DefaultAccess {}
+
+impl<T: Default> CellAccess<T> for DefaultAccess {
+    fn with<F, R>(cell: &StdCell<T>, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        f(&TakeGuard::new(cell, cell.take()))
+    }
+
+    fn with_mut<F, R>(cell: &StdCell<T>, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        f(&mut TakeGuard::new(cell, cell.take()))
+    }
+}
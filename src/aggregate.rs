@@ -0,0 +1,205 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::{Cell, CellAccess, CopyAccess};
+use core::cell::Cell as StdCell;
+
+macro_rules! impl_index_methods {
+    () => {
+        /// Returns a cell referring to the element at `index`.
+        ///
+        /// The returned cell supports [`CellExt`](crate::CellExt), so this
+        /// can be used to access non-[`Copy`] elements by reference.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `index` is out of bounds.
+        pub fn index_cell(&self, index: usize) -> &StdCell<T> {
+            let cell: &StdCell<[T]> = &self.0;
+            &cell.as_slice_of_cells()[index]
+        }
+
+        /// Sets the element at `index` to `value`.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `index` is out of bounds.
+        pub fn set_index(&self, index: usize, value: T) {
+            self.index_cell(index).set(value);
+        }
+
+        /// Gets the element at `index`.
+        ///
+        /// Unlike [`Cell::get`], this copies only the element, not the
+        /// whole collection.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `index` is out of bounds.
+        pub fn get_index(&self, index: usize) -> T
+        where
+            T: Copy,
+        {
+            self.index_cell(index).get()
+        }
+
+        /// Calls `f` with a reference to the element at `index`.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `index` is out of bounds.
+        pub fn with_index<F, R>(&self, index: usize, f: F) -> R
+        where
+            T: Copy,
+            F: FnOnce(&T) -> R,
+        {
+            CopyAccess::with(self.index_cell(index), f)
+        }
+
+        /// Calls `f` with a mutable reference to the element at `index`.
+        ///
+        /// Unlike [`Cell::with_mut`], this copies only the element, not the
+        /// whole collection.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `index` is out of bounds.
+        pub fn with_index_mut<F, R>(&self, index: usize, f: F) -> R
+        where
+            T: Copy,
+            F: FnOnce(&mut T) -> R,
+        {
+            CopyAccess::with_mut(self.index_cell(index), f)
+        }
+    };
+}
+
+impl<T> Cell<[T]> {
+    impl_index_methods!();
+}
+
+// Const generics and `array::map` require Rust 1.55.
+#[cfg(feature = "array")]
+#[clippy::msrv = "1.55"]
+impl<T, const N: usize> Cell<[T; N]> {
+    impl_index_methods!();
+}
+
+#[cfg(feature = "array")]
+#[clippy::msrv = "1.55"]
+impl<T, const N: usize> From<Cell<[T; N]>> for [Cell<T>; N] {
+    fn from(cell: Cell<[T; N]>) -> Self {
+        cell.into_inner().map(Cell::new)
+    }
+}
+
+#[cfg(feature = "array")]
+#[clippy::msrv = "1.55"]
+impl<T, const N: usize> From<[Cell<T>; N]> for Cell<[T; N]> {
+    fn from(cells: [Cell<T>; N]) -> Self {
+        Self::new(cells.map(Cell::into_inner))
+    }
+}
+
+// Macros in `#[doc]` attributes require Rust 1.54, but a macro can still be
+// passed as an `expr` fragment.
+macro_rules! with_doc {
+    ($doc:expr, $($item:tt)*) => {
+        #[doc = $doc]
+        $($item)*
+    };
+}
+
+macro_rules! impl_tuple_methods {
+    (
+        ($($type:ident),*),
+        $([
+            $index:tt,
+            $field_type:ident,
+            $get:ident,
+            $set:ident,
+            $with:ident,
+            $with_mut:ident $(,)?
+        ]),* $(,)?
+    ) => {
+        impl<$($type: Copy),*> Cell<($($type,)*)> {$(
+            with_doc! {
+                concat!("Gets field ", stringify!($index), "."),
+                pub fn $get(&self) -> $field_type {
+                    self.get().$index
+                }
+            }
+
+            with_doc! {
+                concat!("Sets field ", stringify!($index), " to `value`."),
+                pub fn $set(&self, value: $field_type) {
+                    self.with_mut(|tuple| tuple.$index = value);
+                }
+            }
+
+            with_doc! {
+                concat!(
+                    "Calls `f` with a reference to field ",
+                    stringify!($index),
+                    ".",
+                ),
+                pub fn $with<F, R>(&self, f: F) -> R
+                where
+                    F: FnOnce(&$field_type) -> R,
+                {
+                    self.with(|tuple| f(&tuple.$index))
+                }
+            }
+
+            with_doc! {
+                concat!(
+                    "Calls `f` with a mutable reference to field ",
+                    stringify!($index),
+                    ".",
+                ),
+                pub fn $with_mut<F, R>(&self, f: F) -> R
+                where
+                    F: FnOnce(&mut $field_type) -> R,
+                {
+                    self.with_mut(|tuple| f(&mut tuple.$index))
+                }
+            }
+        )*}
+    };
+}
+
+impl_tuple_methods!(
+    (A, B),
+    [0, A, get_0, set_0, with_0, with_0_mut],
+    [1, B, get_1, set_1, with_1, with_1_mut],
+);
+
+impl_tuple_methods!(
+    (A, B, C),
+    [0, A, get_0, set_0, with_0, with_0_mut],
+    [1, B, get_1, set_1, with_1, with_1_mut],
+    [2, C, get_2, set_2, with_2, with_2_mut],
+);
+
+impl_tuple_methods!(
+    (A, B, C, D),
+    [0, A, get_0, set_0, with_0, with_0_mut],
+    [1, B, get_1, set_1, with_1, with_1_mut],
+    [2, C, get_2, set_2, with_2, with_2_mut],
+    [3, D, get_3, set_3, with_3, with_3_mut],
+);
@@ -0,0 +1,335 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::{CompareAndSwap, sealed};
+use core::convert::identity;
+use core::fmt;
+use core::sync::atomic::{self, Ordering::SeqCst};
+
+/// A primitive type that can be stored in an [`AtomicCell`].
+///
+/// This trait is sealed and is implemented for [`bool`], [`char`], the
+/// integer types, and the floating-point types, as long as the target
+/// supports atomics of the corresponding size.
+pub trait AtomicPrimitive: Copy + sealed::Sealed {
+    #[doc(hidden)]
+    type Atomic: Send + Sync;
+
+    #[doc(hidden)]
+    fn new(value: Self) -> Self::Atomic;
+
+    #[doc(hidden)]
+    fn into_inner(atomic: Self::Atomic) -> Self;
+
+    #[doc(hidden)]
+    fn load(atomic: &Self::Atomic) -> Self;
+
+    #[doc(hidden)]
+    fn store(atomic: &Self::Atomic, value: Self);
+
+    #[doc(hidden)]
+    fn swap(atomic: &Self::Atomic, value: Self) -> Self;
+
+    #[doc(hidden)]
+    fn compare_exchange(
+        atomic: &Self::Atomic,
+        current: Self,
+        new: Self,
+    ) -> Result<Self, Self>;
+
+    #[doc(hidden)]
+    fn compare_exchange_weak(
+        atomic: &Self::Atomic,
+        current: Self,
+        new: Self,
+    ) -> Result<Self, Self>;
+}
+
+macro_rules! impl_atomic_primitive {
+    ($size:literal, $type:ty, $atomic:ident, $to:expr, $from:expr $(,)?) => {
+        #[cfg(target_has_atomic = $size)]
+        impl sealed::Sealed for $type {}
+
+        #[cfg(target_has_atomic = $size)]
+        impl AtomicPrimitive for $type {
+            type Atomic = atomic::$atomic;
+
+            fn new(value: Self) -> atomic::$atomic {
+                atomic::$atomic::new($to(value))
+            }
+
+            fn into_inner(a: atomic::$atomic) -> Self {
+                $from(a.into_inner())
+            }
+
+            fn load(a: &atomic::$atomic) -> Self {
+                $from(a.load(SeqCst))
+            }
+
+            fn store(a: &atomic::$atomic, value: Self) {
+                a.store($to(value), SeqCst);
+            }
+
+            fn swap(a: &atomic::$atomic, value: Self) -> Self {
+                $from(a.swap($to(value), SeqCst))
+            }
+
+            fn compare_exchange(
+                a: &atomic::$atomic,
+                current: Self,
+                new: Self,
+            ) -> Result<Self, Self> {
+                a.compare_exchange($to(current), $to(new), SeqCst, SeqCst)
+                    .map($from)
+                    .map_err($from)
+            }
+
+            fn compare_exchange_weak(
+                a: &atomic::$atomic,
+                current: Self,
+                new: Self,
+            ) -> Result<Self, Self> {
+                a.compare_exchange_weak($to(current), $to(new), SeqCst, SeqCst)
+                    .map($from)
+                    .map_err($from)
+            }
+        }
+    };
+
+    ($size:literal, $type:ty, $atomic:ident $(,)?) => {
+        impl_atomic_primitive!($size, $type, $atomic, identity, identity);
+    };
+}
+
+impl_atomic_primitive!("8", bool, AtomicBool);
+impl_atomic_primitive!("8", u8, AtomicU8);
+impl_atomic_primitive!("16", u16, AtomicU16);
+impl_atomic_primitive!("32", u32, AtomicU32);
+impl_atomic_primitive!("64", u64, AtomicU64);
+impl_atomic_primitive!("ptr", usize, AtomicUsize);
+impl_atomic_primitive!("8", i8, AtomicI8);
+impl_atomic_primitive!("16", i16, AtomicI16);
+impl_atomic_primitive!("32", i32, AtomicI32);
+impl_atomic_primitive!("64", i64, AtomicI64);
+impl_atomic_primitive!("ptr", isize, AtomicIsize);
+impl_atomic_primitive!("32", f32, AtomicU32, f32::to_bits, f32::from_bits);
+impl_atomic_primitive!("64", f64, AtomicU64, f64::to_bits, f64::from_bits);
+impl_atomic_primitive!("32", char, AtomicU32, u32::from, char_from_u32);
+
+#[cfg(target_has_atomic = "32")]
+fn char_from_u32(value: u32) -> char {
+    // Only valid `char`s are ever stored.
+    core::char::from_u32(value).unwrap()
+}
+
+/// A thread-safe counterpart to [`Cell`](crate::Cell) for primitive types.
+///
+/// This type provides [`get`], [`with`], and [`with_mut`] methods like those
+/// of [`Cell`](crate::Cell), but is implemented with the atomic types in
+/// [`core::sync::atomic`], so it is [`Sync`]. [`with_mut`] is implemented
+/// with a compare-and-exchange loop, so its closure may be called more than
+/// once; it therefore takes an [`FnMut`] rather than an [`FnOnce`], and
+/// closures that move out of their captures must be rewritten when
+/// switching from [`Cell`](crate::Cell).
+///
+/// This type is available only when the crate feature `atomic` is enabled.
+///
+/// [`get`]: Self::get
+/// [`with`]: Self::with
+/// [`with_mut`]: Self::with_mut
+pub struct AtomicCell<T: AtomicPrimitive>(T::Atomic);
+
+impl<T: AtomicPrimitive> AtomicCell<T> {
+    /// Creates a new [`AtomicCell`] with the given value.
+    pub fn new(value: T) -> Self {
+        Self(T::new(value))
+    }
+
+    /// Consumes the cell and returns the value it holds.
+    pub fn into_inner(self) -> T {
+        T::into_inner(self.0)
+    }
+
+    /// Gets the value held by the cell.
+    pub fn get(&self) -> T {
+        T::load(&self.0)
+    }
+
+    /// Sets the value held by the cell.
+    pub fn set(&self, value: T) {
+        T::store(&self.0, value);
+    }
+
+    /// Replaces the value held by the cell, returning the old value.
+    pub fn replace(&self, value: T) -> T {
+        T::swap(&self.0, value)
+    }
+
+    /// Takes the value held by the cell, leaving [`Default::default()`] in
+    /// its place.
+    pub fn take(&self) -> T
+    where
+        T: Default,
+    {
+        self.replace(T::default())
+    }
+
+    /// Calls `f` with a reference to the contents of the cell.
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        f(&self.get())
+    }
+
+    /// Calls `f` with a mutable reference to the contents of the cell.
+    ///
+    /// Unlike [`Cell::with_mut`](crate::Cell::with_mut), `f` may be called
+    /// more than once, so it must be [`FnMut`]. If storing the modified value
+    /// fails, either because another thread modified the cell while `f` was
+    /// running or because the underlying compare-and-exchange failed
+    /// spuriously, the modified value is discarded and `f` is called again
+    /// with the current contents of the cell. The result of the last call
+    /// is returned.
+    pub fn with_mut<F, R>(&self, mut f: F) -> R
+    where
+        F: FnMut(&mut T) -> R,
+    {
+        let mut current = self.get();
+        loop {
+            let mut value = current;
+            let result = f(&mut value);
+            match T::compare_exchange_weak(&self.0, current, value) {
+                Ok(_) => return result,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+}
+
+impl<T: AtomicPrimitive> AtomicCell<T> {
+    /// Sets the value held by the cell to `new` if it is equal to
+    /// `current`.
+    ///
+    /// Returns [`Ok`] with the previous value if the cell was updated, or
+    /// [`Err`] with the current value otherwise. Floating-point values are
+    /// compared bitwise.
+    pub fn compare_and_swap(&self, current: T, new: T) -> Result<T, T> {
+        T::compare_exchange(&self.0, current, new)
+    }
+
+    /// Repeatedly applies `f` to the value held by the cell until the cell
+    /// can be updated without interference, or until `f` returns [`None`].
+    ///
+    /// Returns [`Ok`] with the previous value if the cell was updated, or
+    /// [`Err`] with the current value if `f` returned [`None`].
+    pub fn fetch_update<F>(&self, mut f: F) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>,
+    {
+        let mut current = self.get();
+        while let Some(new) = f(current) {
+            match T::compare_exchange_weak(&self.0, current, new) {
+                Ok(previous) => return Ok(previous),
+                Err(actual) => current = actual,
+            }
+        }
+        Err(current)
+    }
+}
+
+impl<T: AtomicPrimitive> CompareAndSwap<T> for AtomicCell<T> {
+    fn load(&self) -> T {
+        self.get()
+    }
+
+    fn store(&self, value: T) {
+        self.set(value);
+    }
+
+    fn compare_and_swap(&self, current: T, new: T) -> Result<T, T> {
+        AtomicCell::compare_and_swap(self, current, new)
+    }
+
+    fn fetch_update<F>(&self, f: F) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>,
+    {
+        AtomicCell::fetch_update(self, f)
+    }
+}
+
+macro_rules! impl_compare_and_swap {
+    ($size:literal, $type:ty, $atomic:ident $(,)?) => {
+        #[cfg(target_has_atomic = $size)]
+        impl CompareAndSwap<$type> for atomic::$atomic {
+            fn load(&self) -> $type {
+                self.load(SeqCst)
+            }
+
+            fn store(&self, value: $type) {
+                self.store(value, SeqCst);
+            }
+
+            fn compare_and_swap(
+                &self,
+                current: $type,
+                new: $type,
+            ) -> Result<$type, $type> {
+                self.compare_exchange(current, new, SeqCst, SeqCst)
+            }
+
+            fn fetch_update<F>(&self, f: F) -> Result<$type, $type>
+            where
+                F: FnMut($type) -> Option<$type>,
+            {
+                self.fetch_update(SeqCst, SeqCst, f)
+            }
+        }
+    };
+}
+
+impl_compare_and_swap!("8", bool, AtomicBool);
+impl_compare_and_swap!("8", u8, AtomicU8);
+impl_compare_and_swap!("16", u16, AtomicU16);
+impl_compare_and_swap!("32", u32, AtomicU32);
+impl_compare_and_swap!("64", u64, AtomicU64);
+impl_compare_and_swap!("ptr", usize, AtomicUsize);
+impl_compare_and_swap!("8", i8, AtomicI8);
+impl_compare_and_swap!("16", i16, AtomicI16);
+impl_compare_and_swap!("32", i32, AtomicI32);
+impl_compare_and_swap!("64", i64, AtomicI64);
+impl_compare_and_swap!("ptr", isize, AtomicIsize);
+
+impl<T: AtomicPrimitive + Default> Default for AtomicCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: AtomicPrimitive> From<T> for AtomicCell<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: AtomicPrimitive + fmt::Debug> fmt::Debug for AtomicCell<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_tuple("AtomicCell").field(&self.get()).finish()
+    }
+}
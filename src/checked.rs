@@ -0,0 +1,305 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::{AccessError, Cell, CellExt, sealed};
+use core::cell::Cell as StdCell;
+
+/// A [`Cell`] that detects reentrant access.
+///
+/// While a closure passed to [`with`] or [`with_mut`] is running, the cell
+/// is marked as in use, and any other access to the cell (including
+/// [`get`], [`set`], and nested calls to [`with`] and [`with_mut`]) panics
+/// instead of observing a stale or placeholder value.
+///
+/// Fallible versions of these methods, like [`try_with_mut`], return an
+/// [`AccessError`] instead of panicking.
+///
+/// As with [`Cell`], the by-reference methods are inherent for [`Copy`]
+/// types and are provided by [`CheckedCellExt`] for types that are
+/// [`Default`] but not [`Copy`].
+///
+/// [`with`]: Self::with
+/// [`with_mut`]: Self::with_mut
+/// [`get`]: Self::get
+/// [`set`]: Self::set
+/// [`try_with_mut`]: Self::try_with_mut
+#[derive(Default)]
+pub struct CheckedCell<T> {
+    cell: Cell<T>,
+    in_use: StdCell<bool>,
+}
+
+/// Clears the in-use flag of a [`CheckedCell`] when dropped.
+struct InUse<'a>(&'a StdCell<bool>);
+
+impl Drop for InUse<'_> {
+    fn drop(&mut self) {
+        self.0.set(false);
+    }
+}
+
+impl<T> CheckedCell<T> {
+    /// Creates a new [`CheckedCell`] with the given value.
+    pub fn new(value: T) -> Self {
+        Self {
+            cell: Cell::new(value),
+            in_use: StdCell::new(false),
+        }
+    }
+
+    /// Consumes the cell and returns the value it holds.
+    pub fn into_inner(self) -> T {
+        self.cell.into_inner()
+    }
+
+    /// Returns whether the cell is currently in use by a call to
+    /// [`with`](Self::with) or [`with_mut`](Self::with_mut).
+    pub fn in_use(&self) -> bool {
+        self.in_use.get()
+    }
+
+    /// Sets the value held by the cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is in use.
+    pub fn set(&self, value: T) {
+        self.check();
+        self.cell.set(value);
+    }
+
+    /// Replaces the value held by the cell, returning the old value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is in use.
+    pub fn replace(&self, value: T) -> T {
+        self.check();
+        self.cell.replace(value)
+    }
+
+    /// Gets a mutable reference to the value held by the cell.
+    ///
+    /// This never panics, as the exclusive borrow guarantees the cell
+    /// isn’t in use.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.cell.get_mut()
+    }
+
+    fn check(&self) {
+        if self.in_use.get() {
+            reentrant_access(AccessError);
+        }
+    }
+
+    fn try_acquire(&self) -> Result<InUse<'_>, AccessError> {
+        if self.in_use.replace(true) {
+            return Err(AccessError);
+        }
+        Ok(InUse(&self.in_use))
+    }
+
+    fn acquire(&self) -> InUse<'_> {
+        self.try_acquire().unwrap_or_else(|e| reentrant_access(e))
+    }
+}
+
+#[cold]
+fn reentrant_access(error: AccessError) -> ! {
+    panic!("`CheckedCell` accessed reentrantly: {}", error);
+}
+
+impl<T: Default> CheckedCell<T> {
+    /// Takes the value held by the cell, leaving [`Default::default()`] in
+    /// its place.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is in use.
+    pub fn take(&self) -> T {
+        self.check();
+        self.cell.take()
+    }
+}
+
+impl<T: Copy> CheckedCell<T> {
+    /// Gets the value held by the cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is in use.
+    pub fn get(&self) -> T {
+        self.check();
+        self.cell.get()
+    }
+
+    /// Calls `f` with a reference to the contents of the cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is already in use.
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        let _in_use = self.acquire();
+        self.cell.with(f)
+    }
+
+    /// Calls `f` with a mutable reference to the contents of the cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is already in use.
+    pub fn with_mut<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let _in_use = self.acquire();
+        self.cell.with_mut(f)
+    }
+
+    /// Calls `f` with a reference to the contents of the cell, or returns
+    /// an error if the cell is already in use.
+    pub fn try_with<F, R>(&self, f: F) -> Result<R, AccessError>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        let _in_use = self.try_acquire()?;
+        Ok(self.cell.with(f))
+    }
+
+    /// Calls `f` with a mutable reference to the contents of the cell, or
+    /// returns an error if the cell is already in use.
+    pub fn try_with_mut<F, R>(&self, f: F) -> Result<R, AccessError>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let _in_use = self.try_acquire()?;
+        Ok(self.cell.with_mut(f))
+    }
+}
+
+/// Provides additional methods for [`CheckedCell`]s holding non-[`Copy`]
+/// types.
+pub trait CheckedCellExt<T>: sealed::Sealed {
+    /// Gets the value held by the cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is already in use.
+    fn get(&self) -> T
+    where
+        T: Clone + Default;
+
+    /// Calls `f` with a reference to the contents of the cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is already in use.
+    fn with<F, R>(&self, f: F) -> R
+    where
+        T: Default,
+        F: FnOnce(&T) -> R;
+
+    /// Calls `f` with a mutable reference to the contents of the cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is already in use.
+    fn with_mut<F, R>(&self, f: F) -> R
+    where
+        T: Default,
+        F: FnOnce(&mut T) -> R;
+
+    /// Calls `f` with a reference to the contents of the cell, or returns
+    /// an error if the cell is already in use.
+    fn try_with<F, R>(&self, f: F) -> Result<R, AccessError>
+    where
+        T: Default,
+        F: FnOnce(&T) -> R;
+
+    /// Calls `f` with a mutable reference to the contents of the cell, or
+    /// returns an error if the cell is already in use.
+    fn try_with_mut<F, R>(&self, f: F) -> Result<R, AccessError>
+    where
+        T: Default,
+        F: FnOnce(&mut T) -> R;
+}
+
+impl<T> sealed::Sealed for CheckedCell<T> {}
+
+impl<T> CheckedCellExt<T> for CheckedCell<T> {
+    fn get(&self) -> T
+    where
+        T: Clone + Default,
+    {
+        CheckedCellExt::with(self, T::clone)
+    }
+
+    fn with<F, R>(&self, f: F) -> R
+    where
+        T: Default,
+        F: FnOnce(&T) -> R,
+    {
+        let _in_use = self.acquire();
+        CellExt::with(&self.cell, f)
+    }
+
+    fn with_mut<F, R>(&self, f: F) -> R
+    where
+        T: Default,
+        F: FnOnce(&mut T) -> R,
+    {
+        let _in_use = self.acquire();
+        CellExt::with_mut(&self.cell, f)
+    }
+
+    fn try_with<F, R>(&self, f: F) -> Result<R, AccessError>
+    where
+        T: Default,
+        F: FnOnce(&T) -> R,
+    {
+        let _in_use = self.try_acquire()?;
+        Ok(CellExt::with(&self.cell, f))
+    }
+
+    fn try_with_mut<F, R>(&self, f: F) -> Result<R, AccessError>
+    where
+        T: Default,
+        F: FnOnce(&mut T) -> R,
+    {
+        let _in_use = self.try_acquire()?;
+        Ok(CellExt::with_mut(&self.cell, f))
+    }
+}
+
+impl<T> From<T> for CheckedCell<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> From<Cell<T>> for CheckedCell<T> {
+    fn from(cell: Cell<T>) -> Self {
+        Self {
+            cell,
+            in_use: StdCell::new(false),
+        }
+    }
+}
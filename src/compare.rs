@@ -0,0 +1,139 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::Cell;
+
+/// Atomic-style operations shared by [`Cell`] and atomic types.
+///
+/// This trait uses the vocabulary of [`core::sync::atomic`], so code can be
+/// written generically over single-threaded [`Cell`]s and real atomics.
+/// When the crate feature `atomic` is enabled, this trait is also
+/// implemented for `AtomicCell` and the integer and [`bool`] atomic types
+/// in [`core::sync::atomic`] (using [`SeqCst`] ordering).
+///
+/// The methods of this trait share their names with inherent methods of the
+/// atomic types (including the deprecated three-argument
+/// `compare_and_swap`), which method-call syntax always resolves to first.
+/// The trait is therefore meant to be used through generic code, as in
+/// `fn f<C: CompareAndSwap<u8>>(cell: &C)`, or with fully qualified calls
+/// like `CompareAndSwap::load(&atomic)`.
+///
+/// How values are compared depends on the implementation. [`Cell`] uses
+/// [`PartialEq`], so in [`compare_and_swap`], a NaN `current` never matches,
+/// and `-0.0` matches `0.0`. `AtomicCell` compares floating-point values
+/// bitwise, so a NaN matches a NaN with the same bits, and `-0.0` doesn’t
+/// match `0.0`. For the integer and [`bool`] types, both are the same.
+///
+/// [`SeqCst`]: core::sync::atomic::Ordering::SeqCst
+/// [`compare_and_swap`]: Self::compare_and_swap
+pub trait CompareAndSwap<T> {
+    /// Gets the current value.
+    fn load(&self) -> T;
+
+    /// Sets the value to `value`.
+    fn store(&self, value: T);
+
+    /// Sets the value to `new` if the current value is equal to `current`.
+    ///
+    /// Returns [`Ok`] with the previous value if the value was updated, or
+    /// [`Err`] with the current value otherwise.
+    fn compare_and_swap(&self, current: T, new: T) -> Result<T, T>;
+
+    /// Repeatedly applies `f` to the current value until the value can be
+    /// updated without interference, or until `f` returns [`None`].
+    ///
+    /// Returns [`Ok`] with the previous value if the value was updated, or
+    /// [`Err`] with the current value if `f` returned [`None`].
+    fn fetch_update<F>(&self, f: F) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>;
+}
+
+impl<T: Copy + PartialEq> Cell<T> {
+    /// Sets the value held by the cell to `new` if it is equal to
+    /// `current`.
+    ///
+    /// Returns [`Ok`] with the previous value if the cell was updated, or
+    /// [`Err`] with the current value otherwise.
+    pub fn compare_and_swap(&self, current: T, new: T) -> Result<T, T> {
+        let value = self.get();
+        if value == current {
+            self.set(new);
+            Ok(value)
+        } else {
+            Err(value)
+        }
+    }
+
+    /// Applies `f` to the value held by the cell and stores the result, if
+    /// any.
+    ///
+    /// If `f` modifies the cell (e.g., through a reentrant call), the
+    /// update is considered lost: the result of `f` is discarded, and `f`
+    /// is called again with the new value. Returns [`Ok`] with the previous
+    /// value if the cell was updated, or [`Err`] with the current value if
+    /// `f` returned [`None`].
+    ///
+    /// Whether the cell was modified is determined with [`PartialEq`], except
+    /// that a value that isn’t equal to itself (like NaN) is considered
+    /// unchanged if it is still not equal to itself, so `f` isn’t retried
+    /// forever.
+    pub fn fetch_update<F>(&self, mut f: F) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>,
+    {
+        let mut current = self.get();
+        while let Some(new) = f(current) {
+            let actual = self.get();
+            if unchanged(current, actual) {
+                self.set(new);
+                return Ok(current);
+            }
+            current = actual;
+        }
+        Err(current)
+    }
+}
+
+/// Compares with [`PartialEq`], but treats any two values that aren’t equal
+/// to themselves as equal.
+#[allow(clippy::eq_op)]
+fn unchanged<T: PartialEq>(old: T, new: T) -> bool {
+    old == new || (old != old && new != new)
+}
+
+impl<T: Copy + PartialEq> CompareAndSwap<T> for Cell<T> {
+    fn load(&self) -> T {
+        self.get()
+    }
+
+    fn store(&self, value: T) {
+        self.set(value);
+    }
+
+    fn compare_and_swap(&self, current: T, new: T) -> Result<T, T> {
+        Cell::compare_and_swap(self, current, new)
+    }
+
+    fn fetch_update<F>(&self, f: F) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>,
+    {
+        Cell::fetch_update(self, f)
+    }
+}
@@ -0,0 +1,145 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::{Cell, CellAccess, DefaultAccess};
+use core::cell::Cell as StdCell;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+
+/// A [`Cell`] whose trait implementations work with [`Default`] types.
+///
+/// [`Cell<T>`] implements traits like [`Clone`], [`Debug`](fmt::Debug), and
+/// [`PartialEq`] only when `T` is [`Copy`]. This wrapper instead
+/// implements them when `T` is [`Default`], by temporarily taking the value
+/// out of the cell (as [`CellExt::with`](crate::CellExt::with) does). This
+/// allows, e.g., a `DefaultCell<Vec<u8>>` to be used in a struct that
+/// derives those traits.
+///
+/// This type dereferences to [`Cell<T>`], so all of [`Cell`]’s methods are
+/// available.
+///
+/// Comparing a cell with itself (e.g., `a == a`) is handled correctly, but
+/// as with [`CellExt::with`](crate::CellExt::with), accessing a cell
+/// reentrantly from within a trait method of `T` (e.g., from `T`’s
+/// [`Debug`](fmt::Debug) implementation) will observe
+/// [`Default::default()`].
+#[derive(Default)]
+pub struct DefaultCell<T>(Cell<T>);
+
+impl<T> DefaultCell<T> {
+    /// Creates a new [`DefaultCell`] with the given value.
+    pub fn new(value: T) -> Self {
+        Self(Cell::new(value))
+    }
+
+    fn std_cell(&self) -> &StdCell<T> {
+        &self.0
+    }
+}
+
+impl<T> Deref for DefaultCell<T> {
+    type Target = Cell<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for DefaultCell<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> From<T> for DefaultCell<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> From<Cell<T>> for DefaultCell<T> {
+    fn from(cell: Cell<T>) -> Self {
+        Self(cell)
+    }
+}
+
+impl<T> From<DefaultCell<T>> for Cell<T> {
+    fn from(cell: DefaultCell<T>) -> Self {
+        cell.0
+    }
+}
+
+impl<T: Default> DefaultCell<T> {
+    /// Calls `f` with references to the contents of `self` and `other`,
+    /// which may be the same cell.
+    fn with2<F, R>(&self, other: &Self, f: F) -> R
+    where
+        F: FnOnce(&T, &T) -> R,
+    {
+        DefaultAccess::with(self.std_cell(), |a| {
+            if ptr::eq(self, other) {
+                f(a, a)
+            } else {
+                DefaultAccess::with(other.std_cell(), |b| f(a, b))
+            }
+        })
+    }
+}
+
+impl<T: Clone + Default> Clone for DefaultCell<T> {
+    fn clone(&self) -> Self {
+        Self::new(DefaultAccess::get(self.std_cell()))
+    }
+}
+
+impl<T: fmt::Debug + Default> fmt::Debug for DefaultCell<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        DefaultAccess::with(self.std_cell(), |value| {
+            fmt.debug_struct("Cell").field("value", value).finish()
+        })
+    }
+}
+
+impl<T: Ord + Default> Ord for DefaultCell<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.with2(other, T::cmp)
+    }
+}
+
+impl<T: PartialOrd + Default> PartialOrd for DefaultCell<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.with2(other, T::partial_cmp)
+    }
+}
+
+impl<T: PartialEq + Default> PartialEq for DefaultCell<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.with2(other, T::eq)
+    }
+}
+
+impl<T: Eq + Default> Eq for DefaultCell<T> {}
+
+impl<T: Hash + Default> Hash for DefaultCell<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        DefaultAccess::with(self.std_cell(), |value| value.hash(state));
+    }
+}
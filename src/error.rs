@@ -0,0 +1,40 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use core::fmt;
+
+/// An error returned when a cell is accessed while it is already in use.
+///
+/// This error is returned by methods like [`CheckedCell::try_with_mut`]
+/// when called reentrantly. When the `std` feature is enabled, this type
+/// implements [`Error`][std-error].
+///
+/// [std-error]: https://doc.rust-lang.org/std/error/trait.Error.html
+/// [`CheckedCell::try_with_mut`]: crate::CheckedCell::try_with_mut
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AccessError;
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("cell is already in use")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AccessError {}
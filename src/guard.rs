@@ -0,0 +1,110 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::Cell;
+use core::cell::Cell as StdCell;
+use core::fmt;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+
+/// A value taken out of a cell, which is stored back into the cell when
+/// the guard is dropped.
+///
+/// This is returned by [`Cell::take_guard`] and [`Cell::copy_guard`]. Unlike
+/// [`with_mut`](Cell::with_mut), which passes the value to a closure, a
+/// guard can be held across `?`, early returns, and `.await` points. The
+/// value is stored back even if the guard is dropped during unwinding.
+///
+/// While the guard exists, the cell holds a placeholder (the default value
+/// for [`take_guard`](Cell::take_guard), or a stale copy for
+/// [`copy_guard`](Cell::copy_guard)), and anything stored in the cell in
+/// the meantime is overwritten when the guard is dropped.
+///
+/// To keep the placeholder in the cell instead, use
+/// [`TakeGuard::into_inner`] or [`TakeGuard::forget`].
+pub struct TakeGuard<'a, T> {
+    cell: &'a StdCell<T>,
+    value: Option<T>,
+}
+
+impl<'a, T> TakeGuard<'a, T> {
+    pub(crate) fn new(cell: &'a StdCell<T>, value: T) -> Self {
+        Self {
+            cell,
+            value: Some(value),
+        }
+    }
+
+    /// Returns the value without storing it back into the cell.
+    ///
+    /// This is an associated function rather than a method so that it
+    /// doesn’t shadow methods of `T`.
+    pub fn into_inner(mut this: Self) -> T {
+        // `value` is `None` only after `drop` or this function has run.
+        this.value.take().unwrap()
+    }
+
+    /// Drops the value without storing it back into the cell.
+    pub fn forget(this: Self) {
+        mem::drop(Self::into_inner(this));
+    }
+}
+
+impl<T> Deref for TakeGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().unwrap()
+    }
+}
+
+impl<T> DerefMut for TakeGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_mut().unwrap()
+    }
+}
+
+impl<T> Drop for TakeGuard<'_, T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take() {
+            self.cell.set(value);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for TakeGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: Default> Cell<T> {
+    /// Takes the value out of the cell, leaving [`Default::default()`] in
+    /// its place, and returns a guard that stores it back when dropped.
+    pub fn take_guard(&self) -> TakeGuard<'_, T> {
+        TakeGuard::new(&self.0, self.0.take())
+    }
+}
+
+impl<T: Copy> Cell<T> {
+    /// Returns a guard holding a copy of the value in the cell, which stores
+    /// the (possibly modified) copy back when dropped.
+    pub fn copy_guard(&self) -> TakeGuard<'_, T> {
+        TakeGuard::new(&self.0, self.0.get())
+    }
+}
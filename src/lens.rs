@@ -0,0 +1,253 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::{Cell, CellAccess, CopyAccess};
+use core::cell::Cell as StdCell;
+use core::marker::PhantomData;
+
+/// Focuses on a part (of type `F`) of a larger value (of type `S`).
+///
+/// Lenses are typically created with the [`lens!`](crate::lens!) macro and
+/// used with [`Cell::project`] or [`CellExt::project`] to access a single
+/// field of a cell’s contents. Lenses can be composed with
+/// [`then`](Self::then).
+///
+/// [`CellExt::project`]: crate::CellExt::project
+pub trait Lens<S, F> {
+    /// Calls `f` with a reference to the focused part of `source`.
+    fn with<R, G>(&self, source: &S, f: G) -> R
+    where
+        G: FnOnce(&F) -> R;
+
+    /// Calls `f` with a mutable reference to the focused part of `source`.
+    fn modify<R, G>(&self, source: &mut S, f: G) -> R
+    where
+        G: FnOnce(&mut F) -> R;
+
+    /// Gets a clone of the focused part of `source`.
+    fn get(&self, source: &S) -> F
+    where
+        F: Clone,
+    {
+        self.with(source, F::clone)
+    }
+
+    /// Sets the focused part of `source` to `value`.
+    fn set(&self, source: &mut S, value: F) {
+        self.modify(source, |target| *target = value);
+    }
+
+    /// Composes this lens with `other`, which focuses on a part of this
+    /// lens’s target.
+    fn then<L, G>(self, other: L) -> Then<Self, L, F>
+    where
+        Self: Sized,
+        L: Lens<F, G>,
+    {
+        Then {
+            first: self,
+            second: other,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// A [`Lens`] defined by a pair of accessor functions.
+///
+/// This is the type of lens created by [`lens!`](crate::lens!).
+pub struct Field<S, F> {
+    get: fn(&S) -> &F,
+    get_mut: fn(&mut S) -> &mut F,
+}
+
+impl<S, F> Field<S, F> {
+    /// Creates a new lens from a pair of accessor functions.
+    pub fn new(get: fn(&S) -> &F, get_mut: fn(&mut S) -> &mut F) -> Self {
+        Self {
+            get,
+            get_mut,
+        }
+    }
+}
+
+impl<S, F> Clone for Field<S, F> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S, F> Copy for Field<S, F> {}
+
+impl<S, F> Lens<S, F> for Field<S, F> {
+    fn with<R, G>(&self, source: &S, f: G) -> R
+    where
+        G: FnOnce(&F) -> R,
+    {
+        f((self.get)(source))
+    }
+
+    fn modify<R, G>(&self, source: &mut S, f: G) -> R
+    where
+        G: FnOnce(&mut F) -> R,
+    {
+        f((self.get_mut)(source))
+    }
+}
+
+/// Two composed [`Lens`]es. Returned by [`Lens::then`].
+#[derive(Clone, Copy)]
+pub struct Then<A, B, M> {
+    first: A,
+    second: B,
+    phantom: PhantomData<fn() -> M>,
+}
+
+impl<S, M, F, A, B> Lens<S, F> for Then<A, B, M>
+where
+    A: Lens<S, M>,
+    B: Lens<M, F>,
+{
+    fn with<R, G>(&self, source: &S, f: G) -> R
+    where
+        G: FnOnce(&F) -> R,
+    {
+        self.first.with(source, |middle| self.second.with(middle, f))
+    }
+
+    fn modify<R, G>(&self, source: &mut S, f: G) -> R
+    where
+        G: FnOnce(&mut F) -> R,
+    {
+        self.first.modify(source, |middle| self.second.modify(middle, f))
+    }
+}
+
+/// Creates a [`Lens`] that focuses on a (possibly nested) field of a type.
+///
+/// ```rust
+/// use cell_ref::{lens, Cell};
+///
+/// #[derive(Clone, Copy)]
+/// struct Inner {
+///     count: u32,
+/// }
+///
+/// #[derive(Clone, Copy)]
+/// struct Outer {
+///     inner: Inner,
+///     enabled: bool,
+/// }
+///
+/// let cell = Cell::new(Outer {
+///     inner: Inner {
+///         count: 1,
+///     },
+///     enabled: false,
+/// });
+///
+/// cell.project(lens!(Outer, inner.count)).with_mut(|c| *c += 1);
+/// let enabled = cell.project(lens!(Outer, enabled));
+/// enabled.set(true);
+/// assert!(cell.get().inner.count == 2);
+/// assert!(enabled.get());
+/// ```
+#[macro_export]
+macro_rules! lens {
+    ($type:ty, $($field:tt).+ $(,)?) => {
+        $crate::Field::new(
+            |source: &$type| &source.$($field).+,
+            |source: &mut $type| &mut source.$($field).+,
+        )
+    };
+}
+
+/// A view of part of a cell’s contents, focused by a [`Lens`].
+///
+/// Created by [`Cell::project`] and [`CellExt::project`]. The view accesses
+/// the whole cell with the strategy `A` (see [`CellAccess`]) and then
+/// applies the lens, so for [`Copy`] types, each access still copies the
+/// whole value.
+///
+/// [`CellExt::project`]: crate::CellExt::project
+pub struct CellView<'a, S, F, L, A> {
+    cell: &'a StdCell<S>,
+    lens: L,
+    phantom: PhantomData<fn() -> (F, A)>,
+}
+
+impl<'a, S, F, L, A> CellView<'a, S, F, L, A>
+where
+    L: Lens<S, F>,
+    A: CellAccess<S>,
+{
+    /// Creates a new view of `cell` focused by `lens`.
+    pub fn new(cell: &'a StdCell<S>, lens: L) -> Self {
+        Self {
+            cell,
+            lens,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Gets a clone of the focused value.
+    pub fn get(&self) -> F
+    where
+        F: Clone,
+    {
+        self.with(F::clone)
+    }
+
+    /// Sets the focused value.
+    pub fn set(&self, value: F) {
+        A::with_mut(self.cell, |source| self.lens.set(source, value));
+    }
+
+    /// Calls `f` with a reference to the focused value.
+    pub fn with<G, R>(&self, f: G) -> R
+    where
+        G: FnOnce(&F) -> R,
+    {
+        A::with(self.cell, |source| self.lens.with(source, f))
+    }
+
+    /// Calls `f` with a mutable reference to the focused value.
+    pub fn with_mut<G, R>(&self, f: G) -> R
+    where
+        G: FnOnce(&mut F) -> R,
+    {
+        A::with_mut(self.cell, |source| self.lens.modify(source, f))
+    }
+
+    /// Narrows this view with another lens.
+    pub fn project<M, G>(self, lens: M) -> CellView<'a, S, G, Then<L, M, F>, A>
+    where
+        M: Lens<F, G>,
+    {
+        CellView::new(self.cell, self.lens.then(lens))
+    }
+}
+
+impl<S: Copy> Cell<S> {
+    /// Returns a view of the part of the cell’s contents focused by `lens`.
+    pub fn project<L, F>(&self, lens: L) -> CellView<'_, S, F, L, CopyAccess>
+    where
+        L: Lens<S, F>,
+    {
+        CellView::new(self, lens)
+    }
+}
@@ -27,11 +27,43 @@
 //! [`set`][std-set], but [through an extension trait][cell-ext], this crate
 //! provides those same operations for types that are [`Default`] but not
 //! [`Copy`]. A [`get`] method is also available for types that are both
-//! [`Default`] and [`Clone`].
+//! [`Default`] and [`Clone`]. The extension trait is implemented for the
+//! standard library’s [`Cell`][std-cell] too. These two approaches are
+//! available to generic code through the [`CellAccess`] trait.
+//! [`DefaultCell`] wraps [`Cell`] to implement traits like
+//! [`Debug`](fmt::Debug) and [`PartialEq`] for [`Default`] types.
+//!
+//! [`CheckedCell`] offers the same interface, but panics if the cell is
+//! accessed reentrantly (e.g., from within a call to [`with_mut`]) rather
+//! than exposing a stale or default value. For types that are neither
+//! [`Copy`] nor [`Default`], [`OptionCell`] provides the same by-reference
+//! methods, with the same reentrancy checks. (A plain [`Cell`] can’t offer
+//! these methods for types that are only [`Clone`], as there would be
+//! nothing to leave in the cell while the value is borrowed; see
+//! [its documentation](Cell#types-that-are-only-clone).)
 //!
 //! This crate depends only on [`core`], so it can be used inside `no_std`
 //! environments.
 //!
+//! Crate features
+//! --------------
+//!
+//! * `array`: Provides element access for `Cell<[T; N]>` and conversions
+//!   between `Cell<[T; N]>` and `[Cell<T>; N]`. Requires Rust 1.55 or
+//!   later. Without this feature, element access is still available by
+//!   coercing `&Cell<[T; N]>` to `&Cell<[T]>`.
+//! * `atomic`: Provides `AtomicCell`, a thread-safe counterpart to [`Cell`]
+//!   for primitive types. Requires Rust 1.60 or later.
+//! * `derive`: Provides `#[derive(CellFields)]`, which generates per-field
+//!   accessors for a [`Cell`] holding a struct, and `#[interior]`, which
+//!   turns `&mut self` methods into `&self` methods over a struct’s
+//!   [`Cell`] fields. Requires Rust 1.71 or later.
+//! * `serde`: Implements [`Serialize`][serde-ser] and
+//!   [`Deserialize`][serde-de] for [`Cell`], and provides the
+//!   `serde_default` module for [`Default`] types.
+//! * `std`: Provides `SyncCell`, a thread-safe counterpart to [`Cell`] for
+//!   any type, and implements [`Error`][std-error] for [`AccessError`].
+//!
 //! Example
 //! -------
 //!
@@ -55,27 +87,146 @@
 //! [std-get]: StdCell::get
 //! [std-set]: StdCell::set
 //! [`get`]: Cell::get
+//! [std-error]: https://doc.rust-lang.org/std/error/trait.Error.html
+//! [serde-ser]: https://docs.rs/serde/1/serde/trait.Serialize.html
+//! [serde-de]: https://docs.rs/serde/1/serde/trait.Deserialize.html
+
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
+// Lets the `CellFields` derive, which refers to `::cell_ref`, be used in
+// this crate’s tests.
+#[cfg(all(test, feature = "derive"))]
+extern crate self as cell_ref;
 
 use core::cell::Cell as StdCell;
 use core::cmp::Ordering;
 use core::fmt;
+use core::hash::{Hash, Hasher};
 use core::ops::{Deref, DerefMut};
 
+mod access;
+mod aggregate;
+// The `atomic` feature requires Rust 1.60 for `cfg(target_has_atomic)`.
+#[cfg(feature = "atomic")]
+#[clippy::msrv = "1.60"]
+mod atomic;
+mod checked;
+mod compare;
+mod default_cell;
+mod error;
+mod guard;
+mod lens;
+mod multi;
+mod num;
+mod option;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(feature = "std")]
+mod sync;
+pub use access::{CellAccess, CopyAccess, DefaultAccess};
+#[cfg(feature = "atomic")]
+pub use atomic::{AtomicCell, AtomicPrimitive};
+#[cfg(feature = "derive")]
+pub use cell_ref_derive::{CellFields, interior};
+pub use checked::{CheckedCell, CheckedCellExt};
+pub use compare::CompareAndSwap;
+pub use default_cell::DefaultCell;
+pub use error::AccessError;
+pub use guard::TakeGuard;
+pub use lens::{CellView, Field, Lens, Then};
+pub use num::{BoolCellExt, IntCellExt, NumCellExt};
+pub use option::{CellOptionExt, OptionCell};
+#[cfg(feature = "serde")]
+pub use serde_impl::serde_default;
+#[cfg(feature = "std")]
+pub use sync::SyncCell;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use super::multi::{cell_range, check_distinct};
+}
+
 #[cfg(test)]
 mod tests;
 
 /// A `Cell` type with methods for by-reference mutation and inspection.
+///
+/// This type is guaranteed to have the same memory layout as the standard
+/// library’s [`Cell`](StdCell), which it dereferences to. Like the standard
+/// cell, `T` may be unsized: a `&Cell<[T; N]>` coerces to a `&Cell<[T]>`,
+/// whose [`as_slice_of_cells`](StdCell::as_slice_of_cells) method (through
+/// [`Deref`]) returns standard cells that support [`CellExt`].
+///
+/// # Types that are only `Clone`
+///
+/// The by-reference methods are inherent for [`Copy`] types and provided by
+/// [`CellExt`] for [`Default`] types, but there is no equivalent for types
+/// that are [`Clone`] and nothing else. Without `unsafe` code, the only way
+/// to reach the value in a cell is to move it out, which requires another
+/// value to put in its place, and a [`Clone`] type has no way to produce
+/// one. For such types, either pass the replacement value explicitly with
+/// [`with_using`](Self::with_using), [`with_mut_using`](Self::with_mut_using),
+/// and [`get_using`](Self::get_using), or use [`OptionCell`], which uses
+/// [`None`] as the replacement.
 #[derive(Default)]
-pub struct Cell<T>(StdCell<T>);
+#[repr(transparent)]
+pub struct Cell<T: ?Sized>(StdCell<T>);
 
 impl<T> Cell<T> {
     /// Creates a new [`Cell`] with the given value.
     pub fn new(value: T) -> Self {
         Self(StdCell::new(value))
     }
+
+    /// Consumes the cell and returns the value it holds.
+    pub fn into_inner(self) -> T {
+        self.0.into_inner()
+    }
+
+    /// Calls `f` with a reference to the contents of the cell, temporarily
+    /// storing `placeholder` in the cell.
+    ///
+    /// This works for any type, but if the cell is accessed from within
+    /// `f`, it will contain `placeholder`. The placeholder is dropped once
+    /// the original value is stored back, even if `f` panics.
+    pub fn with_using<F, R>(&self, placeholder: T, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        f(&TakeGuard::new(&self.0, self.replace(placeholder)))
+    }
+
+    /// Calls `f` with a mutable reference to the contents of the cell,
+    /// temporarily storing `placeholder` in the cell.
+    ///
+    /// This works for any type, but if the cell is accessed from within
+    /// `f`, it will contain `placeholder`. The (possibly modified) value is
+    /// stored back in the cell, and the placeholder dropped, even if `f`
+    /// panics.
+    pub fn with_mut_using<F, R>(&self, placeholder: T, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        f(&mut TakeGuard::new(&self.0, self.replace(placeholder)))
+    }
+
+    /// Gets a clone of the value held by the cell, temporarily storing
+    /// `placeholder` in the cell.
+    ///
+    /// This is useful for types that are [`Clone`] but neither [`Copy`] nor
+    /// [`Default`], as reading such a value out of a cell requires some
+    /// other value to take its place. For [`Copy`] and [`Default`] types,
+    /// use [`Cell::get`] or [`CellExt::get`] instead.
+    pub fn get_using(&self, placeholder: T) -> T
+    where
+        T: Clone,
+    {
+        self.with_using(placeholder, T::clone)
+    }
 }
 
-impl<T> Deref for Cell<T> {
+impl<T: ?Sized> Deref for Cell<T> {
     type Target = StdCell<T>;
 
     fn deref(&self) -> &Self::Target {
@@ -83,7 +234,7 @@ impl<T> Deref for Cell<T> {
     }
 }
 
-impl<T> DerefMut for Cell<T> {
+impl<T: ?Sized> DerefMut for Cell<T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.0
     }
@@ -118,18 +269,41 @@ impl<T: Copy> Cell<T> {
     where
         F: FnOnce(&T) -> R,
     {
-        f(&self.get())
+        CopyAccess::with(&self.0, f)
     }
 
     /// Calls `f` with a mutable reference to the contents of the cell.
+    ///
+    /// If `f` panics, the (possibly modified) value is still stored back in
+    /// the cell.
     pub fn with_mut<F, R>(&self, f: F) -> R
     where
         F: FnOnce(&mut T) -> R,
+    {
+        CopyAccess::with_mut(&self.0, f)
+    }
+
+    /// Calls `f` with a mutable reference to a copy of the contents of the
+    /// cell, storing the modified value back only if `f` returns [`Ok`].
+    ///
+    /// The cell keeps its original value while `f` runs. If `f` returns
+    /// [`Err`] or panics, the cell is left unchanged.
+    pub fn with_mut_if_ok<F, R, E>(&self, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut T) -> Result<R, E>,
     {
         let mut value = self.get();
-        let result = f(&mut value);
+        let result = f(&mut value)?;
         self.set(value);
-        result
+        Ok(result)
+    }
+
+    /// Updates the value held by the cell by applying `f` to it.
+    pub fn update<F>(&self, f: F)
+    where
+        F: FnOnce(T) -> T,
+    {
+        self.set(f(self.get()));
     }
 }
 
@@ -138,6 +312,11 @@ mod sealed {
 }
 
 /// Provides additional methods for non-[`Copy`] types.
+///
+/// This trait is implemented for both [`Cell`] and the standard library’s
+/// [`Cell`](StdCell), so these methods can also be used on standard cells,
+/// including those obtained through [`StdCell::from_mut`] or
+/// [`StdCell::as_slice_of_cells`].
 pub trait CellExt<T>: sealed::Sealed {
     /// Gets the value held by the cell.
     fn get(&self) -> T
@@ -145,26 +324,57 @@ pub trait CellExt<T>: sealed::Sealed {
         T: Clone + Default;
 
     /// Calls `f` with a reference to the contents of the cell.
+    ///
+    /// If `f` panics, the value is still stored back in the cell.
     fn with<F, R>(&self, f: F) -> R
     where
         T: Default,
         F: FnOnce(&T) -> R;
 
     /// Calls `f` with a mutable reference to the contents of the cell.
+    ///
+    /// If `f` panics, the (possibly modified) value is still stored back in
+    /// the cell.
     fn with_mut<F, R>(&self, f: F) -> R
     where
         T: Default,
         F: FnOnce(&mut T) -> R;
+
+    /// Replaces the value held by the cell with the result of applying `f`
+    /// to it.
+    ///
+    /// While `f` is running, and afterward if `f` panics, the cell holds
+    /// [`Default::default()`].
+    fn replace_with<F>(&self, f: F)
+    where
+        T: Default,
+        F: FnOnce(T) -> T;
+
+    /// Calls `f` with a mutable reference to a clone of the contents of the
+    /// cell, storing the modified value back only if `f` returns [`Ok`].
+    ///
+    /// The cell keeps its original value while `f` runs. If `f` returns
+    /// [`Err`] or panics, the cell is left unchanged.
+    fn with_mut_if_ok<F, R, E>(&self, f: F) -> Result<R, E>
+    where
+        T: Clone + Default,
+        F: FnOnce(&mut T) -> Result<R, E>;
+
+    /// Returns a view of the part of the cell’s contents focused by `lens`.
+    fn project<L, F>(&self, lens: L) -> CellView<'_, T, F, L, DefaultAccess>
+    where
+        T: Default,
+        L: Lens<T, F>;
 }
 
-impl<T> sealed::Sealed for Cell<T> {}
+impl<T: ?Sized> sealed::Sealed for Cell<T> {}
 
 impl<T> CellExt<T> for Cell<T> {
     fn get(&self) -> T
     where
         T: Clone + Default,
     {
-        self.with(T::clone)
+        CellExt::get(&self.0)
     }
 
     fn with<F, R>(&self, f: F) -> R
@@ -172,10 +382,7 @@ impl<T> CellExt<T> for Cell<T> {
         T: Default,
         F: FnOnce(&T) -> R,
     {
-        let value = self.take();
-        let result = f(&value);
-        self.set(value);
-        result
+        CellExt::with(&self.0, f)
     }
 
     fn with_mut<F, R>(&self, f: F) -> R
@@ -183,10 +390,85 @@ impl<T> CellExt<T> for Cell<T> {
         T: Default,
         F: FnOnce(&mut T) -> R,
     {
-        let mut value = self.take();
-        let result = f(&mut value);
+        CellExt::with_mut(&self.0, f)
+    }
+
+    fn replace_with<F>(&self, f: F)
+    where
+        T: Default,
+        F: FnOnce(T) -> T,
+    {
+        self.0.replace_with(f);
+    }
+
+    fn with_mut_if_ok<F, R, E>(&self, f: F) -> Result<R, E>
+    where
+        T: Clone + Default,
+        F: FnOnce(&mut T) -> Result<R, E>,
+    {
+        CellExt::with_mut_if_ok(&self.0, f)
+    }
+
+    fn project<L, F>(&self, lens: L) -> CellView<'_, T, F, L, DefaultAccess>
+    where
+        T: Default,
+        L: Lens<T, F>,
+    {
+        CellView::new(&self.0, lens)
+    }
+}
+
+impl<T> sealed::Sealed for StdCell<T> {}
+
+impl<T> CellExt<T> for StdCell<T> {
+    fn get(&self) -> T
+    where
+        T: Clone + Default,
+    {
+        DefaultAccess::get(self)
+    }
+
+    fn with<F, R>(&self, f: F) -> R
+    where
+        T: Default,
+        F: FnOnce(&T) -> R,
+    {
+        DefaultAccess::with(self, f)
+    }
+
+    fn with_mut<F, R>(&self, f: F) -> R
+    where
+        T: Default,
+        F: FnOnce(&mut T) -> R,
+    {
+        DefaultAccess::with_mut(self, f)
+    }
+
+    fn replace_with<F>(&self, f: F)
+    where
+        T: Default,
+        F: FnOnce(T) -> T,
+    {
+        self.set(f(self.take()));
+    }
+
+    fn with_mut_if_ok<F, R, E>(&self, f: F) -> Result<R, E>
+    where
+        T: Clone + Default,
+        F: FnOnce(&mut T) -> Result<R, E>,
+    {
+        let mut value = CellExt::get(self);
+        let result = f(&mut value)?;
         self.set(value);
-        result
+        Ok(result)
+    }
+
+    fn project<L, F>(&self, lens: L) -> CellView<'_, T, F, L, DefaultAccess>
+    where
+        T: Default,
+        L: Lens<T, F>,
+    {
+        CellView::new(self, lens)
     }
 }
 
@@ -221,3 +503,9 @@ impl<T: PartialEq + Copy> PartialEq for Cell<T> {
 }
 
 impl<T: Eq + Copy> Eq for Cell<T> {}
+
+impl<T: Hash + Copy> Hash for Cell<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get().hash(state);
+    }
+}
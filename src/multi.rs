@@ -0,0 +1,130 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use core::cell::Cell as StdCell;
+use core::mem;
+use core::ops::Range;
+
+/// Calls a closure with mutable references to the contents of several cells
+/// at once.
+///
+/// `with_cells!(a, b, ... => |x, y, ...| body)` takes the value out of each
+/// cell, runs `body` with each closure parameter bound to a mutable
+/// reference to the corresponding value, and stores the values back, even
+/// if `body` panics. As with a closure, `return` and `?` in `body` exit
+/// only `body`. Each [`Cell`](crate::Cell) is accessed with
+/// [`with_mut`](crate::Cell::with_mut) for [`Copy`] types, or
+/// [`CellExt::with_mut`](crate::CellExt::with_mut) otherwise. Standard
+/// library cells are always accessed with
+/// [`CellExt::with_mut`](crate::CellExt::with_mut), so their contents must
+/// implement [`Default`], even if they’re [`Copy`]. Each cell expression is
+/// evaluated once.
+///
+/// # Panics
+///
+/// Panics if any two cells overlap in memory, such as when the same cell is
+/// passed more than once, or when both a `Cell<[T; N]>` and a cell for one
+/// of its elements are passed. This check happens before any cell is
+/// accessed. Cells of zero-sized types are exempt, as they hold no data
+/// that could be lost.
+///
+/// # Example
+///
+/// ```rust
+/// use cell_ref::{with_cells, Cell};
+///
+/// let source = Cell::new(vec![1, 2, 3]);
+/// let dest = Cell::new(Vec::new());
+/// let moved = Cell::new(0_usize);
+///
+/// with_cells!(source, dest, moved => |src, dst, n| {
+///     dst.extend(src.drain(1..));
+///     *n += 2;
+/// });
+/// assert_eq!(source.take(), [1]);
+/// assert_eq!(dest.take(), [2, 3]);
+/// assert_eq!(moved.get(), 2);
+/// ```
+#[macro_export]
+macro_rules! with_cells {
+    ($($cell:expr),+ $(,)? => |$($arg:pat),+ $(,)?| $body:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::CellExt as _;
+        $crate::with_cells!(@bind [] [$($cell),+] [$($arg),+] $body)
+    }};
+
+    (@bind
+        [$($bound:ident)*]
+        [$cell:expr $(, $rest:expr)*]
+        [$($arg:pat),+]
+        $body:expr
+    ) => {
+        match &$cell {
+            cell => $crate::with_cells!(
+                @bind [$($bound)* cell] [$($rest),*] [$($arg),+] $body
+            ),
+        }
+    };
+
+    (@bind [$($bound:ident)*] [] [$($arg:pat),+] $body:expr) => {{
+        $crate::__private::check_distinct(&[
+            $($crate::__private::cell_range($bound)),*
+        ]);
+        $crate::with_cells!(@nest [$($bound)*] [$($arg),+] $body)
+    }};
+
+    (@nest
+        [$cell:ident $($rest:ident)*]
+        [$arg:pat $(, $args:pat)*]
+        $body:expr
+    ) => {
+        $cell.with_mut(|$arg| {
+            $crate::with_cells!(@nest [$($rest)*] [$($args),*] $body)
+        })
+    };
+
+    (@nest [] [] $body:expr) => {
+        $body
+    };
+
+    (@nest [$($cell:ident)*] [$($arg:pat),*] $body:expr) => {
+        ::core::compile_error!(
+            "`with_cells!` needs one closure parameter per cell"
+        )
+    };
+}
+
+/// Returns the range of addresses occupied by `cell`.
+pub fn cell_range<T>(cell: &StdCell<T>) -> Range<usize> {
+    let start = cell as *const StdCell<T> as usize;
+    start..start + mem::size_of::<T>()
+}
+
+/// Panics if any two ranges overlap. Empty ranges never overlap.
+pub fn check_distinct(ranges: &[Range<usize>]) {
+    for (i, a) in ranges.iter().enumerate() {
+        if ranges[..i].iter().any(|b| a.start < b.end && b.start < a.end) {
+            aliased_cells();
+        }
+    }
+}
+
+#[cold]
+fn aliased_cells() -> ! {
+    panic!("`with_cells!` was passed overlapping cells");
+}
@@ -0,0 +1,211 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::{Cell, sealed};
+
+/// Provides arithmetic methods for cells holding integers and floats.
+///
+/// These methods are named after those of the atomic types in
+/// [`core::sync::atomic`], so code can be ported between the two.
+pub trait NumCellExt<T>: sealed::Sealed {
+    /// Adds `value` to the current value, returning the previous value.
+    ///
+    /// For integers, this wraps around on overflow, like the atomic types.
+    fn fetch_add(&self, value: T) -> T;
+
+    /// Subtracts `value` from the current value, returning the previous
+    /// value.
+    ///
+    /// For integers, this wraps around on overflow, like the atomic types.
+    fn fetch_sub(&self, value: T) -> T;
+
+    /// Sets the current value to the maximum of itself and `value`,
+    /// returning the previous value.
+    fn fetch_max(&self, value: T) -> T;
+
+    /// Sets the current value to the minimum of itself and `value`,
+    /// returning the previous value.
+    fn fetch_min(&self, value: T) -> T;
+}
+
+/// Provides overflow-aware arithmetic methods for cells holding integers.
+pub trait IntCellExt<T>: NumCellExt<T> {
+    /// Adds `value` to the current value, returning the new value, or
+    /// [`None`] (leaving the cell unchanged) if overflow occurred.
+    fn checked_add(&self, value: T) -> Option<T>;
+
+    /// Subtracts `value` from the current value, returning the new value,
+    /// or [`None`] (leaving the cell unchanged) if overflow occurred.
+    fn checked_sub(&self, value: T) -> Option<T>;
+
+    /// Adds `value` to the current value, saturating at the numeric bounds,
+    /// and returns the new value.
+    fn saturating_add(&self, value: T) -> T;
+
+    /// Subtracts `value` from the current value, saturating at the numeric
+    /// bounds, and returns the new value.
+    fn saturating_sub(&self, value: T) -> T;
+
+    /// Adds `value` to the current value, wrapping around at the numeric
+    /// bounds, and returns the new value.
+    fn wrapping_add(&self, value: T) -> T;
+
+    /// Subtracts `value` from the current value, wrapping around at the
+    /// numeric bounds, and returns the new value.
+    fn wrapping_sub(&self, value: T) -> T;
+}
+
+/// Provides logical methods for cells holding [`bool`]s.
+///
+/// These methods are named after those of
+/// [`AtomicBool`](core::sync::atomic::AtomicBool), so code can be ported
+/// between the two.
+pub trait BoolCellExt: sealed::Sealed {
+    /// Negates the current value, returning the previous value.
+    fn toggle(&self) -> bool;
+
+    /// Performs a logical “and” with the current value, returning the
+    /// previous value.
+    fn fetch_and(&self, value: bool) -> bool;
+
+    /// Performs a logical “or” with the current value, returning the
+    /// previous value.
+    fn fetch_or(&self, value: bool) -> bool;
+
+    /// Performs a logical “xor” with the current value, returning the
+    /// previous value.
+    fn fetch_xor(&self, value: bool) -> bool;
+}
+
+impl<T: Copy> Cell<T> {
+    fn fetch_with<F>(&self, f: F) -> T
+    where
+        F: FnOnce(T) -> T,
+    {
+        let previous = self.get();
+        self.set(f(previous));
+        previous
+    }
+
+    /// Stores the result of `f`, if any, and returns it. `f` may return
+    /// either `T` or [`Option<T>`].
+    fn apply<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(T) -> R,
+        R: Copy + Into<Option<T>>,
+    {
+        let result = f(self.get());
+        if let Some(value) = result.into() {
+            self.set(value);
+        }
+        result
+    }
+}
+
+macro_rules! impl_int_cell_ext {
+    ($($type:ty),*) => {$(
+        impl NumCellExt<$type> for Cell<$type> {
+            fn fetch_add(&self, value: $type) -> $type {
+                self.fetch_with(|x| x.wrapping_add(value))
+            }
+
+            fn fetch_sub(&self, value: $type) -> $type {
+                self.fetch_with(|x| x.wrapping_sub(value))
+            }
+
+            fn fetch_max(&self, value: $type) -> $type {
+                self.fetch_with(|x| x.max(value))
+            }
+
+            fn fetch_min(&self, value: $type) -> $type {
+                self.fetch_with(|x| x.min(value))
+            }
+        }
+
+        impl IntCellExt<$type> for Cell<$type> {
+            fn checked_add(&self, value: $type) -> Option<$type> {
+                self.apply(|x| x.checked_add(value))
+            }
+
+            fn checked_sub(&self, value: $type) -> Option<$type> {
+                self.apply(|x| x.checked_sub(value))
+            }
+
+            fn saturating_add(&self, value: $type) -> $type {
+                self.apply(|x| x.saturating_add(value))
+            }
+
+            fn saturating_sub(&self, value: $type) -> $type {
+                self.apply(|x| x.saturating_sub(value))
+            }
+
+            fn wrapping_add(&self, value: $type) -> $type {
+                self.apply(|x| x.wrapping_add(value))
+            }
+
+            fn wrapping_sub(&self, value: $type) -> $type {
+                self.apply(|x| x.wrapping_sub(value))
+            }
+        }
+    )*};
+}
+
+impl_int_cell_ext!(u8, u16, u32, u64, u128, usize);
+impl_int_cell_ext!(i8, i16, i32, i64, i128, isize);
+
+macro_rules! impl_float_cell_ext {
+    ($($type:ty),*) => {$(
+        impl NumCellExt<$type> for Cell<$type> {
+            fn fetch_add(&self, value: $type) -> $type {
+                self.fetch_with(|x| x + value)
+            }
+
+            fn fetch_sub(&self, value: $type) -> $type {
+                self.fetch_with(|x| x - value)
+            }
+
+            fn fetch_max(&self, value: $type) -> $type {
+                self.fetch_with(|x| x.max(value))
+            }
+
+            fn fetch_min(&self, value: $type) -> $type {
+                self.fetch_with(|x| x.min(value))
+            }
+        }
+    )*};
+}
+
+impl_float_cell_ext!(f32, f64);
+
+impl BoolCellExt for Cell<bool> {
+    fn toggle(&self) -> bool {
+        self.fetch_with(|x| !x)
+    }
+
+    fn fetch_and(&self, value: bool) -> bool {
+        self.fetch_with(|x| x & value)
+    }
+
+    fn fetch_or(&self, value: bool) -> bool {
+        self.fetch_with(|x| x | value)
+    }
+
+    fn fetch_xor(&self, value: bool) -> bool {
+        self.fetch_with(|x| x ^ value)
+    }
+}
@@ -0,0 +1,271 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::{AccessError, Cell, CellExt, TakeGuard, sealed};
+use core::cell::Cell as StdCell;
+
+/// A cell with by-reference access for any type, including types that are
+/// neither [`Copy`] nor [`Default`].
+///
+/// Internally, the value is stored in an [`Option`], which is temporarily
+/// left empty while a closure passed to [`with`] or [`with_mut`] is
+/// running. Accessing the cell during that time (e.g., from within the
+/// closure) panics, or returns an [`AccessError`] for the fallible methods
+/// like [`try_with_mut`].
+///
+/// [`with`]: Self::with
+/// [`with_mut`]: Self::with_mut
+/// [`try_with_mut`]: Self::try_with_mut
+pub struct OptionCell<T>(StdCell<Option<T>>);
+
+impl<T> OptionCell<T> {
+    /// Creates a new [`OptionCell`] with the given value.
+    pub fn new(value: T) -> Self {
+        Self(StdCell::new(Some(value)))
+    }
+
+    /// Consumes the cell and returns the value it holds.
+    pub fn into_inner(self) -> T {
+        // The value is absent only while borrowed, which can’t be the case
+        // here.
+        self.0.into_inner().unwrap()
+    }
+
+    /// Returns whether the cell is currently in use by a call to
+    /// [`with`](Self::with) or [`with_mut`](Self::with_mut).
+    pub fn in_use(&self) -> bool {
+        self.try_with(|_| ()).is_err()
+    }
+
+    /// Gets a mutable reference to the value held by the cell.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.0.get_mut().as_mut().unwrap()
+    }
+
+    /// Sets the value held by the cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is in use.
+    pub fn set(&self, value: T) {
+        self.replace(value);
+    }
+
+    /// Replaces the value held by the cell, returning the old value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is in use.
+    pub fn replace(&self, value: T) -> T {
+        self.try_replace(value).unwrap_or_else(|(e, _)| in_use(e))
+    }
+
+    /// Replaces the value held by the cell, returning the old value, or
+    /// returns an error if the cell is in use.
+    ///
+    /// On error, `value` is returned alongside the error and the cell is
+    /// left unchanged.
+    pub fn try_replace(&self, value: T) -> Result<T, (AccessError, T)> {
+        match self.0.take() {
+            Some(old) => {
+                self.0.set(Some(value));
+                Ok(old)
+            }
+            None => Err((AccessError, value)),
+        }
+    }
+
+    /// Gets a clone of the value held by the cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is in use.
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        self.with(T::clone)
+    }
+
+    /// Calls `f` with a reference to the contents of the cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is already in use.
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.try_with(f).unwrap_or_else(|e| in_use(e))
+    }
+
+    /// Calls `f` with a mutable reference to the contents of the cell.
+    ///
+    /// If `f` panics, the (possibly modified) value is still stored back in
+    /// the cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is already in use.
+    pub fn with_mut<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        self.try_with_mut(f).unwrap_or_else(|e| in_use(e))
+    }
+
+    /// Calls `f` with a reference to the contents of the cell, or returns
+    /// an error if the cell is already in use.
+    pub fn try_with<F, R>(&self, f: F) -> Result<R, AccessError>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.try_with_mut(|value| f(value))
+    }
+
+    /// Calls `f` with a mutable reference to the contents of the cell, or
+    /// returns an error if the cell is already in use.
+    pub fn try_with_mut<F, R>(&self, f: F) -> Result<R, AccessError>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let value = self.0.take().ok_or(AccessError)?;
+        let mut value = TakeGuard::new(&self.0, Some(value));
+        Ok(f(value.as_mut().unwrap()))
+    }
+}
+
+#[cold]
+fn in_use(error: AccessError) -> ! {
+    panic!("`OptionCell` accessed reentrantly: {}", error);
+}
+
+impl<T: Default> Default for OptionCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> From<T> for OptionCell<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Provides additional methods for cells holding [`Option`]s.
+///
+/// These methods are built on [`CellExt`](crate::CellExt), so while a
+/// closure passed to one of them is running, the cell temporarily holds
+/// [`None`].
+pub trait CellOptionExt<T>: sealed::Sealed {
+    /// Returns whether the cell holds a [`Some`] value.
+    fn is_some(&self) -> bool;
+
+    /// Returns whether the cell holds [`None`].
+    fn is_none(&self) -> bool;
+
+    /// Inserts `value` into the cell, returning the previous contents.
+    fn insert(&self, value: T) -> Option<T>;
+
+    /// Inserts the result of `f` into the cell if it holds [`None`], and
+    /// returns a clone of the cell’s value.
+    ///
+    /// `f` is called without the cell being borrowed. If `f` inserts a
+    /// value into the cell itself, that value is kept, and the result of
+    /// `f` is dropped.
+    fn get_or_insert_with<F>(&self, f: F) -> T
+    where
+        T: Clone,
+        F: FnOnce() -> T;
+
+    /// Takes the value out of the cell if it holds [`Some`] value for which
+    /// `predicate` returns true.
+    fn take_if<P>(&self, predicate: P) -> Option<T>
+    where
+        P: FnOnce(&mut T) -> bool;
+
+    /// Calls `f` with a reference to the cell’s value, if any.
+    fn map_ref<F, U>(&self, f: F) -> Option<U>
+    where
+        F: FnOnce(&T) -> U;
+
+    /// Calls `f` with references to the values of this cell and `other`,
+    /// if both hold [`Some`] values.
+    ///
+    /// If `other` is the same cell as `self`, it appears empty while `f`
+    /// would be called, so this returns [`None`].
+    fn zip_with<U, F, R>(&self, other: &Cell<Option<U>>, f: F) -> Option<R>
+    where
+        F: FnOnce(&T, &U) -> R;
+}
+
+impl<T> CellOptionExt<T> for Cell<Option<T>> {
+    fn is_some(&self) -> bool {
+        self.with(Option::is_some)
+    }
+
+    fn is_none(&self) -> bool {
+        !self.is_some()
+    }
+
+    fn insert(&self, value: T) -> Option<T> {
+        self.replace(Some(value))
+    }
+
+    fn get_or_insert_with<F>(&self, f: F) -> T
+    where
+        T: Clone,
+        F: FnOnce() -> T,
+    {
+        if let Some(value) = self.map_ref(T::clone) {
+            return value;
+        }
+        let value = f();
+        self.with_mut(|option| option.get_or_insert(value).clone())
+    }
+
+    fn take_if<P>(&self, predicate: P) -> Option<T>
+    where
+        P: FnOnce(&mut T) -> bool,
+    {
+        self.with_mut(|option| {
+            if option.as_mut().map_or(false, predicate) {
+                option.take()
+            } else {
+                None
+            }
+        })
+    }
+
+    fn map_ref<F, U>(&self, f: F) -> Option<U>
+    where
+        F: FnOnce(&T) -> U,
+    {
+        self.with(|option| option.as_ref().map(f))
+    }
+
+    fn zip_with<U, F, R>(&self, other: &Cell<Option<U>>, f: F) -> Option<R>
+    where
+        F: FnOnce(&T, &U) -> R,
+    {
+        self.with(|a| {
+            let a = a.as_ref()?;
+            other.with(|b| b.as_ref().map(|b| f(a, b)))
+        })
+    }
+}
@@ -0,0 +1,101 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::{Cell, CellAccess, DefaultAccess, DefaultCell};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+impl<T: Serialize + Copy> Serialize for Cell<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.get().serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Cell<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
+
+impl<T: Serialize + Default> Serialize for DefaultCell<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        DefaultAccess::with(self, |value| value.serialize(serializer))
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for DefaultCell<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
+
+/// Serde support for [`Cell`]s holding non-[`Copy`] types.
+///
+/// [`Cell<T>`] implements [`Serialize`] only when `T` is [`Copy`]. For
+/// types that are [`Default`] but not [`Copy`], either use
+/// [`DefaultCell`], or use this module with serde’s `with` attribute, which
+/// serializes the value through [`CellExt::with`](crate::CellExt::with):
+///
+/// ```rust
+/// use cell_ref::Cell;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Config {
+///     #[serde(with = "cell_ref::serde_default")]
+///     names: Cell<Vec<String>>,
+/// }
+/// ```
+///
+/// This module is available only when the crate feature `serde` is enabled.
+pub mod serde_default {
+    use crate::{Cell, CellExt};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serializes the value held by `cell`.
+    pub fn serialize<T, S>(
+        cell: &Cell<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize + Default,
+        S: Serializer,
+    {
+        cell.with(|value| value.serialize(serializer))
+    }
+
+    /// Deserializes a value and stores it in a new [`Cell`].
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Cell<T>, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Cell::deserialize(deserializer)
+    }
+}
@@ -0,0 +1,115 @@
+/*
+ * Copyright 2022 taylor.fish <contact@taylor.fish>
+ *
+ * This file is part of cell-ref.
+ *
+ * cell-ref is licensed under the Apache License, Version 2.0
+ * (the "License"); you may not use cell-ref except in compliance
+ * with the License. You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use core::fmt;
+use core::mem;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// A thread-safe counterpart to [`Cell`](crate::Cell) for any type.
+///
+/// This type provides the same methods as [`Cell`](crate::Cell) and
+/// [`CellExt`](crate::CellExt), but is implemented with a [`Mutex`], so it
+/// is [`Sync`] and doesn’t require the contents to be [`Copy`] or
+/// [`Default`].
+///
+/// Like [`Cell::with_mut`](crate::Cell::with_mut), if a closure passed to
+/// [`with_mut`](Self::with_mut) panics, the (possibly modified) value
+/// remains in the cell; the cell is never poisoned. Accessing the cell from
+/// within such a closure, however, will deadlock or panic.
+///
+/// This type is available only when the crate feature `std` is enabled.
+#[derive(Default)]
+pub struct SyncCell<T>(Mutex<T>);
+
+impl<T> SyncCell<T> {
+    /// Creates a new [`SyncCell`] with the given value.
+    pub fn new(value: T) -> Self {
+        Self(Mutex::new(value))
+    }
+
+    /// Consumes the cell and returns the value it holds.
+    pub fn into_inner(self) -> T {
+        self.0.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Gets a mutable reference to the value held by the cell.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.0.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, T> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Gets a clone of the value held by the cell.
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        self.lock().clone()
+    }
+
+    /// Sets the value held by the cell.
+    pub fn set(&self, value: T) {
+        *self.lock() = value;
+    }
+
+    /// Replaces the value held by the cell, returning the old value.
+    pub fn replace(&self, value: T) -> T {
+        mem::replace(&mut *self.lock(), value)
+    }
+
+    /// Takes the value held by the cell, leaving [`Default::default()`] in
+    /// its place.
+    pub fn take(&self) -> T
+    where
+        T: Default,
+    {
+        mem::take(&mut *self.lock())
+    }
+
+    /// Calls `f` with a reference to the contents of the cell.
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        f(&self.lock())
+    }
+
+    /// Calls `f` with a mutable reference to the contents of the cell.
+    ///
+    /// If `f` panics, the (possibly modified) value remains in the cell.
+    pub fn with_mut<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        f(&mut self.lock())
+    }
+}
+
+impl<T> From<T> for SyncCell<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SyncCell<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.with(|value| fmt.debug_tuple("SyncCell").field(value).finish())
+    }
+}
@@ -16,8 +16,12 @@
  * limitations under the License.
  */
 
-use super::{Cell, CellExt};
+use super::{AccessError, Cell, CellAccess, CellExt, CheckedCell};
+use super::{
+    CheckedCellExt, CopyAccess, DefaultAccess, DefaultCell, OptionCell,
+};
 use core::cell::Cell as StdCell;
+use std::panic::{AssertUnwindSafe, catch_unwind};
 
 #[derive(Default)]
 struct DefaultType(u8);
@@ -25,6 +29,9 @@ struct DefaultType(u8);
 #[derive(Default, Clone)]
 struct DefaultCloneType(u8);
 
+#[derive(Clone)]
+struct CloneType(u8);
+
 #[test]
 fn copy_type() {
     for cell in [Cell::new(5), 5.into()] {
@@ -64,3 +71,842 @@ fn convert() {
     let c = StdCell::<u8>::from(Cell::new(2));
     assert!(c.get() == 2);
 }
+
+#[test]
+fn checked_copy_type() {
+    let cell = CheckedCell::new(3_u8);
+    cell.with_mut(|x| *x *= 3);
+    assert!(cell.get() == 9);
+    cell.with(|x| assert!(*x == 9));
+    assert!(!cell.in_use());
+}
+
+#[test]
+fn checked_default_type() {
+    let cell = CheckedCell::new(DefaultCloneType(2));
+    cell.with_mut(|x| x.0 += 5);
+    assert!(cell.get().0 == 7);
+    cell.with(|x| assert!(x.0 == 7));
+    assert!(cell.into_inner().0 == 7);
+}
+
+#[test]
+#[should_panic]
+fn checked_reentrant_with_mut() {
+    let cell = CheckedCell::new(DefaultType(1));
+    cell.with_mut(|_| cell.with_mut(|x| x.0 += 1));
+}
+
+#[test]
+#[should_panic]
+fn checked_reentrant_get() {
+    let cell = CheckedCell::new(1_u8);
+    cell.with(|_| cell.get());
+}
+
+#[test]
+#[should_panic]
+fn checked_reentrant_set() {
+    let cell = CheckedCell::new(DefaultType(1));
+    cell.with_mut(|_| cell.set(DefaultType(2)));
+}
+
+#[test]
+fn checked_try_with() {
+    let cell = CheckedCell::new(4_u8);
+    let result = cell.try_with_mut(|x| {
+        *x += 1;
+        cell.try_with(|x| *x)
+    });
+    assert!(result == Ok(Err(AccessError)));
+    assert!(cell.try_with(|x| *x) == Ok(5));
+
+    let cell = CheckedCell::new(DefaultType(4));
+    let result = cell.try_with(|_| cell.try_with_mut(|x| x.0 += 1));
+    assert!(result == Ok(Err(AccessError)));
+    assert!(cell.try_with(|x| x.0) == Ok(4));
+}
+
+#[test]
+fn copy_type_unwind() {
+    let cell = Cell::new(1_u8);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        cell.with_mut(|x| {
+            *x = 2;
+            panic!("test panic");
+        })
+    }));
+    assert!(result.is_err());
+    assert!(cell.get() == 2);
+}
+
+#[test]
+fn default_type_unwind() {
+    let cell = Cell::new(DefaultCloneType(1));
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        cell.with_mut(|x| {
+            x.0 = 2;
+            panic!("test panic");
+        })
+    }));
+    assert!(result.is_err());
+    assert!(cell.get().0 == 2);
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        cell.with(|_| panic!("test panic"));
+    }));
+    assert!(result.is_err());
+    assert!(cell.get().0 == 2);
+}
+
+#[test]
+fn option_cell() {
+    let cell = OptionCell::new(CloneType(3));
+    cell.with_mut(|x| x.0 += 2);
+    assert!(cell.get().0 == 5);
+    assert!(cell.replace(CloneType(8)).0 == 5);
+    cell.with(|x| assert!(x.0 == 8));
+    assert!(cell.into_inner().0 == 8);
+}
+
+#[test]
+fn option_cell_reentrant() {
+    let cell = OptionCell::new(CloneType(1));
+    let result = cell.try_with_mut(|x| {
+        x.0 += 1;
+        assert!(cell.in_use());
+        match cell.try_replace(CloneType(7)) {
+            Err((AccessError, value)) => assert!(value.0 == 7),
+            Ok(_) => panic!("replaced a cell in use"),
+        }
+        cell.try_with(|x| x.0)
+    });
+    assert!(result == Ok(Err(AccessError)));
+    assert!(cell.try_replace(CloneType(4)).map(|x| x.0).ok() == Some(2));
+    assert!(!cell.in_use());
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        cell.with_mut(|x| {
+            x.0 += 1;
+            cell.set(CloneType(0));
+        })
+    }));
+    assert!(result.is_err());
+    assert!(cell.get().0 == 5);
+}
+
+#[test]
+fn placeholder() {
+    let cell = Cell::new(CloneType(1));
+    cell.with_mut_using(CloneType(0), |x| {
+        x.0 += 6;
+        cell.with_using(CloneType(9), |y| assert!(y.0 == 0));
+    });
+    cell.with_using(CloneType(0), |x| assert!(x.0 == 7));
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        cell.with_mut_using(CloneType(0), |x| {
+            x.0 = 2;
+            panic!("test panic");
+        })
+    }));
+    assert!(result.is_err());
+    assert!(cell.replace(CloneType(0)).0 == 2);
+}
+
+#[test]
+fn get_using() {
+    let cell = Cell::new(CloneType(3));
+    assert!(cell.get_using(CloneType(0)).0 == 3);
+    assert!(cell.get_using(CloneType(0)).0 == 3);
+    assert!(cell.into_inner().0 == 3);
+}
+
+#[test]
+fn access_strategy() {
+    fn increment<A: CellAccess<u8>>(cell: &StdCell<u8>) -> u8 {
+        A::with_mut(cell, |x| *x += 1);
+        A::get(cell)
+    }
+
+    let cell = StdCell::new(1);
+    assert!(increment::<CopyAccess>(&cell) == 2);
+    assert!(increment::<DefaultAccess>(&cell) == 3);
+    let cell = Cell::new(5);
+    assert!(increment::<DefaultAccess>(&cell) == 6);
+}
+
+#[cfg(feature = "atomic")]
+#[test]
+fn atomic_cell() {
+    use super::AtomicCell;
+    use std::sync::Arc;
+    use std::thread;
+
+    let cell = AtomicCell::new(1.5_f64);
+    cell.with_mut(|x| *x *= 2.0);
+    assert!(cell.get() == 3.0);
+    assert!(cell.replace(0.5) == 3.0);
+    cell.with(|x| assert!(*x == 0.5));
+
+    let cell = Arc::new(AtomicCell::new(0_u32));
+    let threads: std::vec::Vec<_> = (0..4)
+        .map(|_| {
+            let cell = cell.clone();
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    cell.with_mut(|x| *x += 1);
+                }
+            })
+        })
+        .collect();
+    threads.into_iter().for_each(|t| t.join().unwrap());
+    assert!(cell.get() == 4000);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn sync_cell() {
+    use super::SyncCell;
+    use std::vec::Vec;
+
+    let cell = SyncCell::new(Vec::new());
+    cell.with_mut(|v| v.push(1));
+    assert!(cell.get() == [1]);
+    assert!(cell.replace(std::vec![2, 3]) == [1]);
+    cell.with(|v| assert!(v.len() == 2));
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        cell.with_mut(|v| {
+            v.push(4);
+            panic!("test panic");
+        })
+    }));
+    assert!(result.is_err());
+    assert!(cell.take() == [2, 3, 4]);
+    assert!(cell.into_inner().is_empty());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    use serde::{Deserialize, Serialize};
+    use std::string::String;
+    use std::vec::Vec;
+
+    #[derive(Serialize, Deserialize)]
+    struct Config {
+        count: Cell<u32>,
+        #[serde(with = "super::serde_default")]
+        names: Cell<Vec<String>>,
+        tags: DefaultCell<Vec<String>>,
+    }
+
+    let config = Config {
+        count: Cell::new(3),
+        names: Cell::new(std::vec!["a".into(), "b".into()]),
+        tags: DefaultCell::new(std::vec!["c".into()]),
+    };
+    let json = serde_json::to_string(&config).unwrap();
+    assert!(json == r#"{"count":3,"names":["a","b"],"tags":["c"]}"#);
+    config.names.with(|names| assert!(names.len() == 2));
+    config.tags.with(|tags| assert!(tags.len() == 1));
+
+    let config: Config = serde_json::from_str(&json).unwrap();
+    assert!(config.count.get() == 3);
+    assert!(config.names.take() == ["a", "b"]);
+    assert!(config.tags.take() == ["c"]);
+}
+
+#[test]
+fn default_cell_traits() {
+    use core::hash::{Hash, Hasher};
+    use std::collections::hash_map::DefaultHasher;
+    use std::vec::Vec;
+
+    fn hash<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct Wrapper(DefaultCell<Vec<u8>>);
+
+    let a = Wrapper(DefaultCell::new(std::vec![1, 2]));
+    let b = Wrapper(std::vec![1, 3].into());
+    assert!(std::format!("{:?}", a) == "Wrapper(Cell { value: [1, 2] })");
+    assert!(a == a);
+    assert!(a != b);
+    assert!(a < b);
+    assert!(b.cmp(&b) == core::cmp::Ordering::Equal);
+    b.0.with_mut(|v| v[1] = 2);
+    assert!(a == b);
+    assert!(hash(&a) == hash(&b));
+    assert!(hash(&Cell::new(5_u8)) == hash(&5_u8));
+}
+
+#[test]
+fn default_cell_clone() {
+    use std::vec::Vec;
+
+    #[derive(Clone, Default)]
+    struct Node {
+        value: DefaultCell<Vec<u8>>,
+        children: DefaultCell<Vec<Node>>,
+    }
+
+    let leaf = Node {
+        value: DefaultCell::new(std::vec![2]),
+        children: DefaultCell::default(),
+    };
+    let root = Node {
+        value: DefaultCell::new(std::vec![1]),
+        children: DefaultCell::new(std::vec![leaf.clone(), leaf]),
+    };
+    let copy = root.clone();
+    root.children.with_mut(|c| c[0].value.set(std::vec![3]));
+    assert!(copy.value.get() == [1]);
+    copy.children.with(|c| {
+        assert!(c.len() == 2);
+        assert!(c.iter().all(|n| n.value.get() == [2]));
+    });
+    root.children.with(|c| assert!(c[0].value.get() == [3]));
+}
+
+#[test]
+fn by_value_update() {
+    let cell = Cell::new(3_u8);
+    cell.update(|x| x * 4);
+    assert!(cell.get() == 12);
+
+    #[derive(Debug, PartialEq)]
+    enum State {
+        Idle,
+        Running(u8),
+    }
+
+    impl Default for State {
+        fn default() -> Self {
+            Self::Idle
+        }
+    }
+
+    let cell = Cell::new(State::Idle);
+    let step = |s| match s {
+        State::Idle => State::Running(0),
+        State::Running(n) => State::Running(n + 1),
+    };
+    cell.replace_with(step);
+    cell.replace_with(step);
+    assert!(cell.take() == State::Running(1));
+
+    cell.set(State::Running(5));
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        cell.replace_with(|_| panic!("test panic"));
+    }));
+    assert!(result.is_err());
+    assert!(cell.take() == State::Idle);
+}
+
+#[test]
+fn transactional_with_mut() {
+    let cell = Cell::new(10_u8);
+    let result: Result<_, ()> = cell.with_mut_if_ok(|x| {
+        *x = x.checked_sub(4).ok_or(())?;
+        Ok(*x)
+    });
+    assert!(result == Ok(6));
+    let result: Result<(), ()> = cell.with_mut_if_ok(|x| {
+        *x = 0;
+        Err(())
+    });
+    assert!(result.is_err());
+    assert!(cell.get() == 6);
+
+    let cell = Cell::new(DefaultCloneType(1));
+    let result: Result<u8, ()> = cell.with_mut_if_ok(|x| {
+        x.0 += 1;
+        Ok(x.0)
+    });
+    assert!(result == Ok(2));
+    let result = cell.with_mut_if_ok(|x| {
+        x.0 = 0;
+        assert!(CellExt::get(&cell).0 == 2);
+        Err("invalid")
+    });
+    assert!(result == Err::<(), _>("invalid"));
+    assert!(CellExt::get(&cell).0 == 2);
+}
+
+#[test]
+fn compare_and_swap() {
+    use super::CompareAndSwap;
+
+    fn increment<C: CompareAndSwap<u8>>(cell: &C) -> Result<u8, u8> {
+        cell.fetch_update(|x| x.checked_add(1))
+    }
+
+    let cell = Cell::new(1_u8);
+    assert!(cell.compare_and_swap(2, 5) == Err(1));
+    assert!(cell.compare_and_swap(1, 5) == Ok(1));
+    assert!(increment(&cell) == Ok(5));
+    cell.set(u8::MAX);
+    assert!(increment(&cell) == Err(u8::MAX));
+
+    let mut calls = 0;
+    cell.set(0);
+    let result = cell.fetch_update(|x| {
+        calls += 1;
+        if calls == 1 {
+            cell.set(10);
+        }
+        Some(x + 1)
+    });
+    assert!(result == Ok(10));
+    assert!(calls == 2);
+    assert!(cell.get() == 11);
+
+    let cell = Cell::new(f64::NAN);
+    assert!(cell.compare_and_swap(f64::NAN, 1.0).is_err());
+    assert!(cell.fetch_update(|x| Some(x + 1.0)).map_or(false, f64::is_nan));
+    assert!(cell.get().is_nan());
+    cell.set(1.0);
+    let result = cell.fetch_update(|x| {
+        if x == 1.0 {
+            cell.set(f64::NAN);
+        }
+        Some(2.0)
+    });
+    assert!(result.map_or(false, f64::is_nan));
+    assert!(cell.get() == 2.0);
+
+    #[cfg(feature = "atomic")]
+    {
+        use core::sync::atomic::AtomicU8;
+        let cell = super::AtomicCell::new(3_u8);
+        assert!(cell.compare_and_swap(3, 4) == Ok(3));
+        assert!(increment(&cell) == Ok(4));
+        let atomic = AtomicU8::new(7);
+        assert!(increment(&atomic) == Ok(7));
+        assert!(CompareAndSwap::load(&atomic) == 8);
+    }
+}
+
+#[test]
+fn num_cell() {
+    use super::{BoolCellExt, IntCellExt, NumCellExt};
+
+    let cell = Cell::new(250_u8);
+    assert!(cell.fetch_add(2) == 250);
+    assert!(cell.checked_add(3) == Some(u8::MAX));
+    assert!(cell.checked_add(1).is_none());
+    assert!(cell.fetch_add(1) == u8::MAX);
+    assert!(cell.get() == 0);
+    assert!(cell.saturating_sub(1) == 0);
+    assert!(cell.wrapping_sub(1) == u8::MAX);
+    assert!(cell.fetch_min(7) == u8::MAX);
+    assert!(cell.fetch_max(5) == 7);
+    assert!(cell.get() == 7);
+
+    let cell = Cell::new(1.5_f32);
+    assert!(cell.fetch_sub(0.5) == 1.5);
+    assert!(cell.fetch_max(4.0) == 1.0);
+    assert!(cell.get() == 4.0);
+
+    let cell = Cell::new(false);
+    assert!(!cell.toggle());
+    assert!(cell.fetch_and(false));
+    assert!(!cell.fetch_or(true));
+    assert!(cell.fetch_xor(true));
+    assert!(!cell.get());
+}
+
+#[test]
+fn cell_option() {
+    use super::CellOptionExt;
+
+    let cell = Cell::new(None::<DefaultCloneType>);
+    assert!(cell.is_none());
+    let value = cell.get_or_insert_with(|| {
+        cell.insert(DefaultCloneType(1));
+        DefaultCloneType(2)
+    });
+    assert!(value.0 == 1);
+    assert!(cell.is_some());
+    assert!(cell.map_ref(|x| x.0 + 1) == Some(2));
+    cell.with(|_| assert!(cell.is_none()));
+
+    let other = Cell::new(Some(CloneType(5)));
+    assert!(cell.zip_with(&other, |a, b| a.0 + b.0) == Some(6));
+    assert!(cell.zip_with(&cell, |a, b| a.0 + b.0).is_none());
+
+    assert!(cell.take_if(|x| x.0 > 1).is_none());
+    assert!(cell.take_if(|x| x.0 == 1).map(|x| x.0) == Some(1));
+    assert!(cell.is_none());
+}
+
+#[test]
+fn std_cell_ext() {
+    let mut values = [DefaultCloneType(1), DefaultCloneType(2)];
+    let cells = StdCell::from_mut(&mut values[..]).as_slice_of_cells();
+    cells[0].with_mut(|x| x.0 += 10);
+    cells[1].with(|x| assert!(x.0 == 2));
+    assert!(CellExt::get(&cells[0]).0 == 11);
+    cells[1].replace_with(|x| DefaultCloneType(x.0 * 3));
+    assert!(values[0].0 == 11);
+    assert!(values[1].0 == 6);
+}
+
+#[test]
+fn unsized_cell() {
+    let cell = Cell::new([1_u8, 2, 3]);
+    let slice: &Cell<[u8]> = &cell;
+    let cells = slice.as_slice_of_cells();
+    cells[1].with_mut(|x| *x *= 10);
+    assert!(cells.len() == 3);
+    assert!(cell.into_inner() == [1, 20, 3]);
+
+    let cell = Cell::new(std::vec![1]);
+    cell.with_mut(|v| v.push(2));
+    assert!(cell.into_inner() == [1, 2]);
+}
+
+#[test]
+fn array_and_tuple() {
+    let cell = Cell::new([1_u8, 2, 3]);
+    let slice: &Cell<[u8]> = &cell;
+    slice.with_index_mut(0, |x| *x += 4);
+    slice.set_index(2, 9);
+    assert!(slice.get_index(0) == 5);
+    slice.with_index(1, |x| assert!(*x == 2));
+    assert!(cell.get() == [5, 2, 9]);
+
+    #[cfg(feature = "array")]
+    {
+        cell.with_index_mut(1, |x| *x += 1);
+        assert!(cell.get_index(1) == 3);
+        let cells: [Cell<u8>; 3] = cell.into();
+        cells[1].set(7);
+        let cell: Cell<[u8; 3]> = cells.into();
+        assert!(cell.get() == [5, 7, 9]);
+    }
+
+    let cell = Cell::new([DefaultCloneType(1), DefaultCloneType(2)]);
+    let slice: &Cell<[DefaultCloneType]> = &cell;
+    slice.index_cell(1).with_mut(|x| x.0 = 6);
+    slice.set_index(0, DefaultCloneType(3));
+    assert!(cell.into_inner().iter().map(|x| x.0).eq([3, 6]));
+
+    let cell = Cell::new((1_u8, 'a', 2.5_f32));
+    cell.with_0_mut(|x| *x += 1);
+    cell.set_1('b');
+    cell.with_2(|x| assert!(*x == 2.5));
+    assert!(cell.get_0() == 2);
+    assert!(cell.get() == (2, 'b', 2.5));
+}
+
+#[test]
+fn lens() {
+    use super::{Lens, lens};
+    use std::string::String;
+
+    #[derive(Clone, Copy)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(Default)]
+    struct Shape {
+        name: String,
+        origin: (u8, u8),
+    }
+
+    let cell = Cell::new(Point {
+        x: 1,
+        y: 2,
+    });
+    let x = cell.project(lens!(Point, x));
+    x.with_mut(|x| *x += 10);
+    x.with(|x| assert!(*x == 11));
+    cell.project(lens!(Point, y)).set(5);
+    assert!(cell.get().x == 11);
+    assert!(cell.get().y == 5);
+
+    let cell = Cell::new(Shape::default());
+    cell.project(lens!(Shape, name)).with_mut(|n| n.push('a'));
+    let origin = lens!(Shape, origin);
+    cell.project(origin).project(lens!((u8, u8), 1)).set(3);
+    cell.project(origin.then(lens!((u8, u8), 0))).with_mut(|x| *x += 1);
+    assert!(cell.project(origin).get() == (1, 3));
+    assert!(cell.project(lens!(Shape, name)).get() == "a");
+
+    let mut shape = cell.take();
+    lens!(Shape, origin.0).set(&mut shape, 9);
+    assert!(lens!(Shape, origin).get(&shape) == (9, 3));
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn cell_fields() {
+    use super::CellFields;
+    use std::string::String;
+
+    #[derive(CellFields, Clone, Copy)]
+    struct Point {
+        x: u8,
+        y: u8,
+    }
+
+    #[derive(CellFields, Default)]
+    struct Shape {
+        name: String,
+        #[cell_fields(skip_get)]
+        points: StdCell<u8>,
+    }
+
+    #[derive(CellFields, Default)]
+    #[cell_fields(default)]
+    struct Wrapper<T> {
+        value: T,
+    }
+
+    #[derive(CellFields, Clone, Copy)]
+    #[cell_fields(copy)]
+    struct Pair<F, R> {
+        first: F,
+        second: R,
+    }
+
+    let cell = Cell::new(Point {
+        x: 1,
+        y: 2,
+    });
+    cell.set_x(3);
+    cell.with_y_mut(|y| *y += 4);
+    assert!(cell.get_x() == 3);
+    assert!(cell.with_y(|y| *y) == 6);
+
+    let cell = Cell::new(Shape::default());
+    cell.with_name_mut(|n| n.push('a'));
+    cell.set_points(StdCell::new(2));
+    assert!(cell.get_name() == "a");
+    assert!(cell.with_points(StdCell::get) == 2);
+
+    let cell = Cell::new(Wrapper::<String>::default());
+    cell.set_value("b".into());
+    assert!(cell.get_value() == "b");
+
+    mod bank {
+        use super::super::CellFields;
+
+        #[derive(CellFields, Clone, Copy, Default)]
+        pub struct Account {
+            pub id: u32,
+            pub(super) owner: u32,
+            balance: u32,
+            #[cell_fields(skip)]
+            pub frozen: bool,
+        }
+
+        impl Account {
+            pub fn balance(&self) -> u32 {
+                self.balance
+            }
+        }
+    }
+
+    // Calls to these methods would be ambiguous if `AccountCellFields`
+    // provided them too.
+    trait NoAccessors {
+        fn set_balance(&self, _: u32) -> bool {
+            true
+        }
+
+        fn set_owner(&self, _: u32) -> bool {
+            true
+        }
+
+        fn set_frozen(&self, _: bool) -> bool {
+            true
+        }
+    }
+
+    impl NoAccessors for Cell<bank::Account> {}
+
+    use bank::AccountCellFields;
+    let cell = Cell::new(bank::Account::default());
+    cell.set_id(4);
+    assert!(cell.get_id() == 4);
+    assert!(cell.set_balance(100));
+    assert!(cell.set_owner(1));
+    assert!(cell.set_frozen(true));
+    assert!(cell.get().balance() == 0);
+    assert!(!cell.get().frozen);
+    cell.with(|a| assert!(a.owner == 0));
+
+    let cell = Cell::new(Pair {
+        first: 1_u8,
+        second: 'a',
+    });
+    cell.with_first_mut(|x| *x += 1);
+    cell.set_second('b');
+    assert!(cell.get_first() == 2);
+    assert!(cell.with_second(|c| *c == 'b'));
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn interior() {
+    use super::interior;
+    use std::vec::Vec;
+
+    #[derive(Default)]
+    struct Stack {
+        items: Cell<Vec<u8>>,
+        popped: Cell<usize>,
+        limit: usize,
+    }
+
+    #[interior(skip(limit))]
+    impl Stack {
+        fn push(&mut self, item: u8) -> Result<(), u8> {
+            if self.items.len() >= self.limit {
+                return Err(item);
+            }
+            self.items.push(item);
+            Ok(())
+        }
+
+        fn pop(&mut self) -> Option<u8> {
+            let item = self.items.pop()?;
+            self.popped += 1;
+            assert!(self.popped > 0, "{}", self.popped);
+            Some(item)
+        }
+
+        fn clear(&mut self) {
+            #[cfg(any())]
+            {
+                self.popped += self.items.len();
+            }
+            self.items.clear();
+        }
+    }
+
+    let stack = Stack {
+        limit: 2,
+        ..Stack::default()
+    };
+    assert!(stack.push(1) == Ok(()));
+    assert!(stack.push(2) == Ok(()));
+    assert!(stack.push(3) == Err(3));
+    assert!(stack.pop() == Some(2));
+    assert!(stack.pop() == Some(1));
+    assert!(stack.pop().is_none());
+    assert!(stack.popped.get() == 2);
+    assert!(stack.push(3) == Ok(()));
+    stack.clear();
+    assert!(stack.popped.get() == 2);
+    assert!(stack.items.take().is_empty());
+}
+
+#[test]
+fn take_guard() {
+    use super::TakeGuard;
+    use std::vec::Vec;
+
+    fn push_all(cell: &Cell<Vec<u8>>, items: &[u8]) -> Result<(), u8> {
+        let mut vec = cell.take_guard();
+        for &item in items {
+            if item == 0 {
+                return Err(item);
+            }
+            vec.push(item);
+        }
+        Ok(())
+    }
+
+    let cell = Cell::new(std::vec![1]);
+    assert!(push_all(&cell, &[2, 3]).is_ok());
+    assert!(push_all(&cell, &[4, 0, 5]).is_err());
+    assert!(cell.with(|v| *v == [1, 2, 3, 4]));
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let mut vec = cell.take_guard();
+        vec.push(5);
+        panic!();
+    }));
+    assert!(result.is_err());
+    assert!(cell.with(Vec::len) == 5);
+
+    let vec = TakeGuard::into_inner(cell.take_guard());
+    assert!(vec.len() == 5);
+    assert!(cell.with(Vec::is_empty));
+
+    let cell = Cell::new(1_u8);
+    let mut value = cell.copy_guard();
+    *value += 1;
+    assert!(cell.get() == 1);
+    drop(value);
+    assert!(cell.get() == 2);
+    let mut value = cell.copy_guard();
+    *value += 1;
+    TakeGuard::forget(value);
+    assert!(cell.get() == 2);
+}
+
+#[test]
+fn with_cells() {
+    use super::with_cells;
+    use std::vec::Vec;
+
+    let source = Cell::new(std::vec![1, 2, 3]);
+    let dest = StdCell::new(Vec::new());
+    let count = Cell::new(0_u8);
+    let result = with_cells!(&source, dest, count => |s, d, c| {
+        d.push(s.pop()?);
+        *c += 1;
+        Some(())
+    });
+    assert!(result == Some(()));
+    assert!(source.with(Vec::len) == 2);
+    assert!(dest.take() == [3]);
+    assert!(count.get() == 1);
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        with_cells!(source, count => |s, c| {
+            s.push(4);
+            *c += 1;
+            panic!();
+        })
+    }));
+    assert!(result.is_err());
+    assert!(source.with(Vec::len) == 3);
+    assert!(count.get() == 2);
+
+    let result = catch_unwind(AssertUnwindSafe(
+        || with_cells!(source, count, source => |_, c, _| *c += 1),
+    ));
+    assert!(result.is_err());
+    assert!(count.get() == 2);
+
+    let array = Cell::new([1_u8, 2, 3]);
+    let slice: &Cell<[u8]> = &array;
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        with_cells!(&array, slice.index_cell(1) => |a, e| {
+            *e = 9;
+            a[1] = 5;
+        })
+    }));
+    assert!(result.is_err());
+    assert!(array.get() == [1, 2, 3]);
+    with_cells!(slice.index_cell(0), slice.index_cell(1) => |a, b| {
+        core::mem::swap(a, b);
+    });
+    assert!(array.get() == [2, 1, 3]);
+
+    let unit = Cell::new(());
+    with_cells!(unit, unit => |_, _| ());
+}